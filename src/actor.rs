@@ -1,5 +1,9 @@
 use crate::{
-    bot::Bot, character::Character, level::UpdateContext, message::Message, player::Player,
+    bot::Bot,
+    character::{Character, Resistances},
+    level::UpdateContext,
+    message::Message,
+    player::Player,
 };
 use rg3d::{
     core::{
@@ -9,10 +13,19 @@ use rg3d::{
         },
         visitor::{Visit, VisitResult, Visitor},
     },
-    scene::Scene,
+    scene::{physics::Physics, Scene},
 };
 use std::ops::{Deref, DerefMut};
 
+/// Actors moving faster than this speed are considered moving when they shoot.
+const MOVING_SPEED: f32 = 0.5;
+
+/// Spread of weapons of moving actors is multiplied by this factor.
+const MOVEMENT_SPREAD_FACTOR: f32 = 2.0;
+
+/// Spread of weapons of aiming player is multiplied by this factor.
+const AIM_SPREAD_FACTOR: f32 = 0.5;
+
 #[allow(clippy::large_enum_variant)]
 pub enum Actor {
     Bot(Bot),
@@ -57,6 +70,28 @@ impl Actor {
     pub fn clean_up(&mut self, scene: &mut Scene) {
         static_dispatch!(self, clean_up, scene)
     }
+
+    /// Returns multipliers of damage the actor takes.
+    pub fn resistances(&self) -> Resistances {
+        static_dispatch!(self, resistances,)
+    }
+
+    /// Returns multiplier of spread of weapons, moving actors shoot less accurately and
+    /// aiming player shoots more accurately.
+    pub fn spread_factor(&self, physics: &Physics) -> f32 {
+        let velocity = physics.bodies.get(self.get_body().into()).unwrap().linvel();
+        let mut factor = if Vector3::new(velocity.x, 0.0, velocity.z).norm() > MOVING_SPEED {
+            MOVEMENT_SPREAD_FACTOR
+        } else {
+            1.0
+        };
+        if let Actor::Player(player) = self {
+            if player.is_aiming() {
+                factor *= AIM_SPREAD_FACTOR;
+            }
+        }
+        factor
+    }
 }
 
 impl Deref for Actor {
@@ -167,10 +202,12 @@ impl ActorContainer {
 
             match actor {
                 Actor::Bot(bot) => bot.update(handle, context, &self.target_descriptors),
-                Actor::Player(player) => player.update(context),
+                Actor::Player(player) => player.update(handle, context),
             }
+            actor.update_hitboxes(context.scene);
             if !is_dead {
-                for (item_handle, item) in context.items.pair_iter() {
+                for (item_handle, item) in context.items.pair_iter().filter(|(_, i)| i.is_active())
+                {
                     let body = context
                         .scene
                         .physics
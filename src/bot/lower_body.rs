@@ -1,5 +1,5 @@
 use crate::{
-    bot::{clean_machine, BotDefinition},
+    bot::{clean_machine, BotAnimations},
     create_play_animation_state, GameTime,
 };
 use rg3d::animation::machine::{Machine, Parameter, State, Transition};
@@ -9,7 +9,6 @@ use rg3d::{
         pool::Handle,
         visitor::{Visit, VisitResult, Visitor},
     },
-    engine::resource_manager::ResourceManager,
     scene::{node::Node, Scene},
 };
 
@@ -52,57 +51,44 @@ impl LowerBodyMachine {
     const WALK_TO_DYING: &'static str = "WalkToDying";
     const IDLE_TO_DYING: &'static str = "IdleToDying";
 
-    pub async fn new(
-        resource_manager: ResourceManager,
-        definition: &BotDefinition,
+    pub fn new(
+        animations: &BotAnimations,
         model: Handle<Node>,
         scene: &mut Scene,
-    ) -> Self {
-        let (
-            idle_animation_resource,
-            walk_animation_resource,
-            scream_animation_resource,
-            dying_animation_resource,
-        ) = rg3d::futures::join!(
-            resource_manager.request_model(definition.idle_animation),
-            resource_manager.request_model(definition.walk_animation),
-            resource_manager.request_model(definition.scream_animation),
-            resource_manager.request_model(definition.dying_animation),
-        );
-
+    ) -> Result<Self, String> {
         let mut machine = Machine::new();
 
         let (_, idle_state) = create_play_animation_state(
-            idle_animation_resource.unwrap(),
+            animations.idle.clone(),
             "Idle",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (walk_animation, walk_state) = create_play_animation_state(
-            walk_animation_resource.unwrap(),
+            animations.walk.clone(),
             "Walk",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (_, scream_state) = create_play_animation_state(
-            scream_animation_resource.unwrap(),
+            animations.scream.clone(),
             "Scream",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (dying_animation, dying_state) = create_play_animation_state(
-            dying_animation_resource.unwrap(),
+            animations.dying.clone(),
             "Dying",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         scene
             .animations
@@ -163,12 +149,12 @@ impl LowerBodyMachine {
 
         machine.set_entry_state(idle_state);
 
-        Self {
+        Ok(Self {
             machine,
             walk_state,
             walk_animation,
             dying_animation,
-        }
+        })
     }
 
     pub fn clean_up(&mut self, scene: &mut Scene) {
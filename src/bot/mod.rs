@@ -4,8 +4,10 @@ use crate::{
         lower_body::{LowerBodyMachine, LowerBodyMachineInput},
         upper_body::{UpperBodyMachine, UpperBodyMachineInput},
     },
-    character::Character,
-    level::UpdateContext,
+    character::{Character, DamageType, Resistances},
+    definition::{self, Definition, Storage},
+    hitbox::{self, HitboxDefinition},
+    level::{door::Door, UpdateContext},
     message::Message,
     weapon::WeaponContainer,
     GameTime,
@@ -17,15 +19,16 @@ use rg3d::{
         color::Color,
         math::{frustum::Frustum, ray::Ray, SmoothAngle, Vector3Ext},
         pool::Handle,
-        rand::Rng,
         visitor::{Visit, VisitResult, Visitor},
     },
     engine::resource_manager::ResourceManager,
+    futures::executor::block_on,
     physics::{
         dynamics::{BodyStatus, RigidBodyBuilder},
         geometry::{ColliderBuilder, InteractionGroups},
     },
-    rand,
+    rand::Rng,
+    resource::model::Model,
     scene::{
         self,
         base::BaseBuilder,
@@ -35,50 +38,38 @@ use rg3d::{
         transform::TransformBuilder,
         Scene, SceneDrawingContext,
     },
-    utils::{
-        log::{Log, MessageKind},
-        navmesh::Navmesh,
-    },
+    utils::navmesh::Navmesh,
 };
+use serde::Deserialize;
 use std::{
     ops::{Deref, DerefMut},
     path::Path,
-    sync::mpsc::Sender,
+    sync::{mpsc::Sender, Arc, RwLock},
 };
 
 mod lower_body;
 mod upper_body;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-pub enum BotKind {
-    Mutant,
-    Parasite,
-    Zombie,
-}
+/// Id of a bot definition, definitions are loaded from `data/bots`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BotKind(u32);
 
 impl BotKind {
     pub fn from_id(id: i32) -> Result<Self, String> {
-        match id {
-            0 => Ok(BotKind::Mutant),
-            1 => Ok(BotKind::Parasite),
-            2 => Ok(BotKind::Zombie),
-            _ => Err(format!("Invalid bot kind {}", id)),
+        match definition::registry::<BotDefinition>().get(id as u32) {
+            Some(definition) => Ok(definition.kind()),
+            None => Err(format!("Invalid bot kind {}", id)),
         }
     }
 
     pub fn id(self) -> i32 {
-        match self {
-            BotKind::Mutant => 0,
-            BotKind::Parasite => 1,
-            BotKind::Zombie => 2,
-        }
+        self.0 as i32
     }
 
-    pub fn description(self) -> &'static str {
-        match self {
-            BotKind::Mutant => "Mutant",
-            BotKind::Parasite => "Parasite",
-            BotKind::Zombie => "Zombie",
+    pub fn description(self) -> String {
+        match definition::registry::<BotDefinition>().get(self.0) {
+            Some(definition) => definition.display_name.clone(),
+            None => format!("Unknown bot {}", self.0),
         }
     }
 }
@@ -114,7 +105,7 @@ pub struct Bot {
     kind: BotKind,
     model: Handle<Node>,
     character: Character,
-    pub definition: &'static BotDefinition,
+    pub definition: Arc<BotDefinition>,
     lower_body_machine: LowerBodyMachine,
     upper_body_machine: UpperBodyMachine,
     last_health: f32,
@@ -149,10 +140,10 @@ impl Default for Bot {
     fn default() -> Self {
         Self {
             character: Default::default(),
-            kind: BotKind::Mutant,
+            kind: Default::default(),
             model: Default::default(),
             target: Default::default(),
-            definition: Self::get_definition(BotKind::Mutant),
+            definition: Default::default(),
             lower_body_machine: Default::default(),
             upper_body_machine: Default::default(),
             last_health: 0.0,
@@ -179,111 +170,214 @@ impl Default for Bot {
     }
 }
 
+#[derive(Deserialize, Default, Debug)]
 pub struct BotDefinition {
+    /// Unique id of the bot, it is stored in saves.
+    pub id: u32,
+    /// Unique name of the bot, markers of level scenes with this name spawn the bot.
+    pub name: String,
+    pub display_name: String,
+
     // Generic parameters.
     pub scale: f32,
     pub health: f32,
-    pub kind: BotKind,
     pub walk_speed: f32,
     pub weapon_scale: f32,
-    pub model: &'static str,
-    pub weapon_hand_name: &'static str,
-    pub left_leg_name: &'static str,
-    pub right_leg_name: &'static str,
-    pub spine: &'static str,
+    pub model: String,
+    pub weapon_hand_name: String,
+    pub left_leg_name: String,
+    pub right_leg_name: String,
+    /// Bone which is rotated to aim vertically, empty if the bot does not aim vertically.
+    #[serde(default)]
+    pub spine: String,
+    #[serde(default)]
     pub v_aim_angle_hack: f32,
+    #[serde(default)]
     pub can_use_weapons: bool,
     pub attack_damage: f32,
+    /// Multipliers of damage the bot takes.
+    #[serde(default)]
+    pub resistances: Resistances,
+    /// Bot without hitboxes is hit by its body.
+    #[serde(default)]
+    pub hitboxes: Vec<HitboxDefinition>,
+    /// Time in seconds from the beginning of attack animation when damage is applied.
     pub attack_timestamp: f32,
 
     // Animations.
-    pub idle_animation: &'static str,
-    pub scream_animation: &'static str,
-    pub attack_animation: &'static str,
-    pub walk_animation: &'static str,
-    pub aim_animation: &'static str,
-    pub dying_animation: &'static str,
+    pub idle_animation: String,
+    pub scream_animation: String,
+    pub attack_animation: String,
+    pub walk_animation: String,
+    /// Empty if the bot cannot use weapons.
+    #[serde(default)]
+    pub aim_animation: String,
+    pub dying_animation: String,
 }
 
-impl Bot {
-    pub fn get_definition(kind: BotKind) -> &'static BotDefinition {
-        match kind {
-            BotKind::Mutant => {
-                static DEFINITION: BotDefinition = BotDefinition {
-                    kind: BotKind::Mutant,
-                    model: "data/models/mutant.FBX",
-                    attack_animation: "data/animations/mutant_attack_swipe.fbx",
-                    scream_animation: "data/animations/mutant_scream.fbx",
-                    idle_animation: "data/animations/mutant_idle.fbx",
-                    walk_animation: "data/animations/mutant_walk.fbx",
-                    aim_animation: "", // Empty because cannot use weapons.
-                    dying_animation: "data/animations/mutant_dying.fbx",
-                    weapon_hand_name: "Mutant:RightHand",
-                    left_leg_name: "Mutant:LeftUpLeg",
-                    right_leg_name: "Mutant:RightUpLeg",
-                    spine: "", // Empty because cannot use weapons.
-                    walk_speed: 0.7,
-                    scale: 0.0065,
-                    weapon_scale: 1.0,
-                    health: 1000.0,
-                    v_aim_angle_hack: 0.0,
-                    can_use_weapons: false,
-                    attack_damage: 120.0,
-                    attack_timestamp: 1.1,
-                };
-                &DEFINITION
+impl BotDefinition {
+    pub fn kind(&self) -> BotKind {
+        BotKind(self.id)
+    }
+}
+
+static DEFINITIONS: Storage<BotDefinition> = RwLock::new(None);
+
+impl Definition for BotDefinition {
+    const PATH: &'static str = "data/bots";
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn storage() -> &'static Storage<Self> {
+        &DEFINITIONS
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.scale <= 0.0 || self.health <= 0.0 || self.walk_speed < 0.0 {
+            return Err(
+                "scale and health must be positive, walk speed can't be negative".to_owned(),
+            );
+        }
+        if self.attack_timestamp < 0.0 {
+            return Err("attack timestamp can't be negative".to_owned());
+        }
+        self.resistances.validate()?;
+        hitbox::validate(&self.hitboxes)?;
+
+        for (field, value) in &[
+            ("model", &self.model),
+            ("weapon_hand_name", &self.weapon_hand_name),
+            ("left_leg_name", &self.left_leg_name),
+            ("right_leg_name", &self.right_leg_name),
+            ("idle_animation", &self.idle_animation),
+            ("scream_animation", &self.scream_animation),
+            ("attack_animation", &self.attack_animation),
+            ("walk_animation", &self.walk_animation),
+            ("dying_animation", &self.dying_animation),
+        ] {
+            if value.is_empty() {
+                return Err(format!("{} is not set", field));
             }
-            BotKind::Parasite => {
-                static DEFINITION: BotDefinition = BotDefinition {
-                    kind: BotKind::Parasite,
-                    model: "data/models/parasite.FBX",
-                    attack_animation: "data/animations/parasite_attack.fbx",
-                    scream_animation: "data/animations/parasite_scream.fbx",
-                    idle_animation: "data/animations/parasite_idle.fbx",
-                    walk_animation: "data/animations/parasite_running.fbx",
-                    aim_animation: "", // Empty because cannot use weapons.
-                    dying_animation: "data/animations/parasite_dying.fbx",
-                    weapon_hand_name: "RightHand",
-                    left_leg_name: "LeftUpLeg",
-                    right_leg_name: "RightUpLeg",
-                    spine: "", // Empty because cannot use weapons.
-                    walk_speed: 1.0,
-                    scale: 0.0055,
-                    weapon_scale: 1.0,
-                    health: 300.0,
-                    v_aim_angle_hack: 0.0,
-                    can_use_weapons: false,
-                    attack_damage: 40.0,
-                    attack_timestamp: 1.1,
-                };
-                &DEFINITION
+        }
+
+        if self.can_use_weapons && self.aim_animation.is_empty() {
+            return Err("aim_animation must be set for a bot that can use weapons".to_owned());
+        }
+
+        Ok(())
+    }
+
+    fn check_assets(&self, resource_manager: &ResourceManager) -> Result<(), String> {
+        // Model is instantiated in a scratch scene, so bones can be found and animations can be
+        // retargeted to it the same way as when the bot is spawned.
+        let mut scene = Scene::new();
+        let model = block_on(resource_manager.request_model(Path::new(&self.model)))
+            .map_err(|e| format!("Unable to load model {}. Reason: {:?}", self.model, e))?
+            .instantiate_geometry(&mut scene);
+
+        let mut bones = vec![
+            &self.weapon_hand_name,
+            &self.left_leg_name,
+            &self.right_leg_name,
+        ];
+        if !self.spine.is_empty() {
+            bones.push(&self.spine);
+        }
+        for hitbox in self.hitboxes.iter() {
+            bones.push(&hitbox.bone);
+            if !hitbox.end_bone.is_empty() {
+                bones.push(&hitbox.end_bone);
             }
-            BotKind::Zombie => {
-                static DEFINITION: BotDefinition = BotDefinition {
-                    kind: BotKind::Parasite,
-                    model: "data/models/zombie.fbx",
-                    attack_animation: "data/animations/zombie_attack.fbx",
-                    scream_animation: "data/animations/zombie_scream.fbx",
-                    idle_animation: "data/animations/zombie_idle.fbx",
-                    walk_animation: "data/animations/zombie_running.fbx",
-                    aim_animation: "data/animations/zombie_aim_rifle.fbx",
-                    dying_animation: "data/animations/zombie_dying.fbx",
-                    weapon_hand_name: "mixamorig5:RightHand",
-                    left_leg_name: "mixamorig5:LeftUpLeg",
-                    right_leg_name: "mixamorig5:RightUpLeg",
-                    spine: "Spine",
-                    walk_speed: 1.2,
-                    scale: 0.0055,
-                    weapon_scale: 1.0,
-                    health: 100.0,
-                    v_aim_angle_hack: 12.0,
-                    can_use_weapons: false,
-                    attack_damage: 40.0,
-                    attack_timestamp: 1.6,
-                };
-                &DEFINITION
+        }
+        if let Some(bone) = bones
+            .into_iter()
+            .find(|bone| scene.graph.find_by_name(model, bone).is_none())
+        {
+            return Err(format!("there is no bone {} in model {}", bone, self.model));
+        }
+
+        let mut animations = vec![
+            &self.idle_animation,
+            &self.scream_animation,
+            &self.attack_animation,
+            &self.walk_animation,
+            &self.dying_animation,
+        ];
+        if self.can_use_weapons {
+            animations.push(&self.aim_animation);
+        }
+        for path in animations {
+            let animation = block_on(load_animation(resource_manager.clone(), path))?;
+            if animation.retarget_animations(model, &mut scene).is_empty() {
+                return Err(format!("there are no animations in {}", path));
             }
         }
+
+        Ok(())
+    }
+}
+
+/// Animations of a bot, they're loaded before the bot is created, so a missing animation is
+/// reported instead of leaving a half-built bot in the scene.
+pub struct BotAnimations {
+    pub idle: Model,
+    pub walk: Model,
+    pub scream: Model,
+    pub attack: Model,
+    pub dying: Model,
+    /// Only bots that can use weapons have aim animation.
+    pub aim: Option<Model>,
+}
+
+async fn load_animation(resource_manager: ResourceManager, path: &str) -> Result<Model, String> {
+    resource_manager
+        .request_model(path)
+        .await
+        .map_err(|e| format!("Unable to load animation {}. Reason: {:?}", path, e))
+}
+
+impl BotAnimations {
+    async fn load(
+        resource_manager: ResourceManager,
+        definition: &BotDefinition,
+    ) -> Result<Self, String> {
+        let (idle, walk, scream, attack, dying) = rg3d::futures::join!(
+            load_animation(resource_manager.clone(), &definition.idle_animation),
+            load_animation(resource_manager.clone(), &definition.walk_animation),
+            load_animation(resource_manager.clone(), &definition.scream_animation),
+            load_animation(resource_manager.clone(), &definition.attack_animation),
+            load_animation(resource_manager.clone(), &definition.dying_animation),
+        );
+
+        let aim = if definition.can_use_weapons {
+            Some(load_animation(resource_manager, &definition.aim_animation).await?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            idle: idle?,
+            walk: walk?,
+            scream: scream?,
+            attack: attack?,
+            dying: dying?,
+            aim,
+        })
+    }
+}
+
+impl Bot {
+    pub fn get_definition(kind: BotKind) -> Arc<BotDefinition> {
+        definition::registry::<BotDefinition>()
+            .get(kind.0)
+            .cloned()
+            .unwrap_or_else(|| panic!("bot definition {} is not loaded", kind.0))
     }
 
     pub async fn new(
@@ -292,16 +386,18 @@ impl Bot {
         scene: &mut Scene,
         position: Vector3<f32>,
         sender: Sender<Message>,
-    ) -> Self {
+    ) -> Result<Self, String> {
         let definition = Self::get_definition(kind);
 
         let body_height = 0.60;
         let body_radius = 0.20;
 
+        let animations = BotAnimations::load(resource_manager.clone(), &definition).await?;
+
         let model = resource_manager
-            .request_model(Path::new(definition.model))
+            .request_model(Path::new(&definition.model))
             .await
-            .unwrap()
+            .map_err(|e| format!("Unable to load model {}. Reason: {:?}", definition.model, e))?
             .instantiate_geometry(scene);
 
         scene.graph[model]
@@ -313,18 +409,19 @@ impl Bot {
                 definition.scale,
             ));
 
-        let spine = scene.graph.find_by_name(model, definition.spine);
-        if spine.is_none() {
-            Log::writeln(
-                MessageKind::Warning,
-                "Spine bone not found, bot won't aim vertically!".to_owned(),
-            );
-        }
+        // Bot without spine bone won't aim vertically.
+        let spine = if definition.spine.is_empty() {
+            Handle::NONE
+        } else {
+            scene.graph.find_by_name(model, &definition.spine)
+        };
 
         let pivot = BaseBuilder::new()
             .with_children(&[model])
             .build(&mut scene.graph);
 
+        let hitboxes = hitbox::create_hitboxes(&definition.hitboxes, model, scene);
+
         let body = scene.physics.add_body(
             RigidBodyBuilder::new(BodyStatus::Dynamic)
                 .lock_rotations()
@@ -340,7 +437,9 @@ impl Bot {
 
         scene.physics_binder.bind(pivot, body.into());
 
-        let hand = scene.graph.find_by_name(model, definition.weapon_hand_name);
+        let hand = scene
+            .graph
+            .find_by_name(model, &definition.weapon_hand_name);
         let wpn_scale = definition.weapon_scale * (1.0 / definition.scale);
         let weapon_pivot = BaseBuilder::new()
             .with_local_transform(
@@ -359,35 +458,44 @@ impl Bot {
 
         scene.graph.link_nodes(weapon_pivot, hand);
 
-        let locomotion_machine =
-            LowerBodyMachine::new(resource_manager.clone(), &definition, model, scene).await;
+        let locomotion_machine = LowerBodyMachine::new(&animations, model, scene)?;
         let combat_machine = UpperBodyMachine::new(
-            resource_manager.clone(),
-            definition,
+            &animations,
+            &definition,
             model,
             scene,
             definition.attack_timestamp,
-        )
-        .await;
+        )?;
 
-        Self {
+        Ok(Self {
             character: Character {
                 pivot,
                 body,
                 weapon_pivot,
                 health: definition.health,
+                hitboxes,
                 sender: Some(sender),
                 ..Default::default()
             },
             spine,
-            definition,
             last_health: definition.health,
+            definition,
             model,
             kind,
             lower_body_machine: locomotion_machine,
             upper_body_machine: combat_machine,
             ..Default::default()
-        }
+        })
+    }
+
+    /// Overrides health from bot definition.
+    pub fn set_health(&mut self, health: f32) {
+        self.character.health = health;
+        self.last_health = health;
+    }
+
+    pub fn kind(&self) -> BotKind {
+        self.kind
     }
 
     pub fn can_be_removed(&self, scene: &Scene) -> bool {
@@ -465,9 +573,17 @@ impl Bot {
         }
     }
 
+    /// Switches to a weapon with ammo when current weapon is empty and can't be reloaded.
     fn select_weapon(&mut self, weapons: &WeaponContainer) {
-        if self.character.current_weapon().is_some()
-            && weapons[self.character.current_weapon()].ammo() == 0
+        let current_weapon = self.character.current_weapon();
+        if current_weapon.is_some()
+            && weapons[current_weapon].ammo() == 0
+            && !weapons[current_weapon].is_reloading()
+            && self
+                .character
+                .inventory
+                .ammo(weapons[current_weapon].ammo_kind())
+                == 0
         {
             for (i, handle) in self.character.weapons().iter().enumerate() {
                 if weapons[*handle].ammo() > 0 {
@@ -531,7 +647,13 @@ impl Bot {
         body.set_position(position, true);
     }
 
-    fn rebuild_path(&mut self, position: Vector3<f32>, navmesh: &mut Navmesh, time: GameTime) {
+    fn rebuild_path(
+        &mut self,
+        position: Vector3<f32>,
+        navmesh: &mut Navmesh,
+        doors: &[Door],
+        level_time: f64,
+    ) {
         if let Some(target) = self.target.as_ref() {
             let from = position - Vector3::new(0.0, 1.0, 0.0);
             if let Some(from_index) = navmesh.query_closest(from) {
@@ -543,7 +665,17 @@ impl Bot {
                         .is_ok()
                     {
                         self.path.reverse();
-                        self.last_path_rebuild_time = time.elapsed;
+                        self.last_path_rebuild_time = level_time;
+
+                        // Navmesh knows nothing about doors, bot walks up to the first door it
+                        // can't open and waits there until somebody opens it.
+                        if let Some(blocked) = self.path.windows(2).position(|segment| {
+                            doors
+                                .iter()
+                                .any(|door| door.blocks_path(segment[0], segment[1]))
+                        }) {
+                            self.path.truncate(blocked + 1);
+                        }
                     }
                 }
             }
@@ -664,6 +796,9 @@ impl Bot {
                             actor: target.handle,
                             who: Default::default(),
                             amount: self.definition.attack_damage,
+                            damage_type: DamageType::Melee,
+                            position: None,
+                            location: None,
                         })
                         .unwrap();
                 }
@@ -687,8 +822,7 @@ impl Bot {
                     ];
                     sender
                         .send(Message::PlaySound {
-                            path: footsteps[rand::thread_rng().gen_range(0..footsteps.len())]
-                                .into(),
+                            path: footsteps[context.rng.gen_range(0..footsteps.len())].into(),
                             position,
                             gain: 1.0,
                             rolloff_factor: 2.0,
@@ -699,11 +833,11 @@ impl Bot {
             }
         }
 
-        if context.time.elapsed - self.last_path_rebuild_time >= 1.0 {
+        if context.level_time - self.last_path_rebuild_time >= 1.0 {
             if context.navmesh.is_some() {
                 let navmesh = &mut context.scene.navmeshes[context.navmesh];
 
-                self.rebuild_path(position, navmesh, context.time);
+                self.rebuild_path(position, navmesh, context.doors, context.level_time);
             }
         }
         self.restoration_time -= context.time.delta;
@@ -758,6 +892,18 @@ impl Bot {
         }
     }
 
+    pub fn resistances(&self) -> Resistances {
+        self.definition.resistances
+    }
+
+    /// Picks up reloaded definition. Values that are baked into the scene when the bot is
+    /// created (model, scale, animations, attack timestamp, shapes of hitboxes) and current
+    /// health stay the same.
+    pub fn refresh_definition(&mut self) {
+        self.definition = Self::get_definition(self.kind);
+        hitbox::refresh_hitboxes(&mut self.character.hitboxes, &self.definition.hitboxes);
+    }
+
     pub fn clean_up(&mut self, scene: &mut Scene) {
         self.upper_body_machine.clean_up(scene);
         self.lower_body_machine.clean_up(scene);
@@ -1,5 +1,5 @@
 use crate::{
-    bot::{clean_machine, BotDefinition},
+    bot::{clean_machine, BotAnimations, BotDefinition},
     create_play_animation_state, GameTime,
 };
 use rg3d::animation::machine::{Machine, Parameter, State, Transition};
@@ -9,7 +9,6 @@ use rg3d::{
         pool::Handle,
         visitor::{Visit, VisitResult, Visitor},
     },
-    engine::resource_manager::ResourceManager,
     scene::{node::Node, Scene},
 };
 
@@ -49,86 +48,62 @@ impl UpperBodyMachine {
     const WALK_TO_DYING: &'static str = "WalkToDying";
     const IDLE_TO_DYING: &'static str = "IdleToDying";
 
-    pub async fn new(
-        resource_manager: ResourceManager,
+    pub fn new(
+        animations: &BotAnimations,
         definition: &BotDefinition,
         model: Handle<Node>,
         scene: &mut Scene,
         attack_timestamp: f32,
-    ) -> Self {
-        let (
-            idle_animation_resource,
-            walk_animation_resource,
-            scream_animation_resource,
-            attack_animation_resource,
-            dying_animation_resource,
-        ) = rg3d::futures::join!(
-            resource_manager.request_model(definition.idle_animation),
-            resource_manager.request_model(definition.walk_animation),
-            resource_manager.request_model(definition.scream_animation),
-            resource_manager.request_model(definition.attack_animation),
-            resource_manager.request_model(definition.dying_animation),
-        );
-
-        let aim_animation_resource =
-            if definition.can_use_weapons && !definition.attack_animation.is_empty() {
-                resource_manager
-                    .request_model(definition.aim_animation)
-                    .await
-                    .ok()
-            } else {
-                None
-            };
-
+    ) -> Result<Self, String> {
         let mut machine = Machine::new();
 
         let (aim_animation, aim_state) = if let Some(aim_animation_resource) =
-            aim_animation_resource.clone()
+            animations.aim.clone()
         {
-            create_play_animation_state(aim_animation_resource, "Aim", &mut machine, scene, model)
+            create_play_animation_state(aim_animation_resource, "Aim", &mut machine, scene, model)?
         } else {
             (Handle::NONE, Handle::NONE)
         };
 
         let (idle_animation, idle_state) = create_play_animation_state(
-            idle_animation_resource.unwrap(),
+            animations.idle.clone(),
             "Idle",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (walk_animation, walk_state) = create_play_animation_state(
-            walk_animation_resource.unwrap(),
+            animations.walk.clone(),
             "Walk",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (scream_animation, scream_state) = create_play_animation_state(
-            scream_animation_resource.unwrap(),
+            animations.scream.clone(),
             "Scream",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (attack_animation, attack_state) = create_play_animation_state(
-            attack_animation_resource.unwrap(),
+            animations.attack.clone(),
             "Attack",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (dying_animation, dying_state) = create_play_animation_state(
-            dying_animation_resource.unwrap(),
+            animations.dying.clone(),
             "Dying",
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         scene
             .animations
@@ -147,7 +122,7 @@ impl UpperBodyMachine {
             .set_loop(false)
             .set_enabled(false);
 
-        for leg_name in &[definition.left_leg_name, definition.right_leg_name] {
+        for leg_name in &[&definition.left_leg_name, &definition.right_leg_name] {
             let leg_node = scene.graph.find_by_name(model, leg_name);
 
             for &animation in &[
@@ -232,7 +207,7 @@ impl UpperBodyMachine {
             0.2,
             Self::SCREAM_TO_IDLE,
         ));
-        if aim_animation_resource.is_some() {
+        if animations.aim.is_some() {
             machine.add_transition(Transition::new(
                 "Idle->Aim",
                 idle_state,
@@ -279,12 +254,12 @@ impl UpperBodyMachine {
 
         machine.set_entry_state(idle_state);
 
-        Self {
+        Ok(Self {
             machine,
             attack_animation,
             aim_state,
             dying_animation,
-        }
+        })
     }
 
     pub fn clean_up(&mut self, scene: &mut Scene) {
@@ -1,4 +1,11 @@
-use crate::{message::Message, weapon::Weapon};
+use crate::{
+    hitbox::Hitbox,
+    inventory::Inventory,
+    item::ItemKind,
+    message::Message,
+    save::{read_version, visit_since},
+    weapon::Weapon,
+};
 use rg3d::{
     core::{
         algebra::Vector3,
@@ -7,16 +14,142 @@ use rg3d::{
     },
     scene::{node::Node, physics::Physics, RigidBodyHandle, Scene},
 };
+use serde::Deserialize;
 use std::sync::mpsc::Sender;
 
+/// Amount of armor an armor shard gives without amount override.
+pub const ARMOR_SHARD_AMOUNT: u32 = 5;
+
+/// Amount of armor an armor vest gives without amount override.
+pub const ARMOR_VEST_AMOUNT: u32 = 100;
+
+/// Tier of armor defines which part of incoming damage armor takes and how much armor a
+/// character can have.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ArmorTier {
+    Light,
+    Heavy,
+}
+
+impl ArmorTier {
+    pub fn id(self) -> u32 {
+        match self {
+            ArmorTier::Light => 0,
+            ArmorTier::Heavy => 1,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(ArmorTier::Light),
+            1 => Ok(ArmorTier::Heavy),
+            _ => Err(format!("Invalid armor tier {}", id)),
+        }
+    }
+
+    /// Part of damage that is absorbed by armor, in [0; 1] range.
+    pub fn absorption(self) -> f32 {
+        match self {
+            ArmorTier::Light => 0.33,
+            ArmorTier::Heavy => 0.66,
+        }
+    }
+
+    pub fn capacity(self) -> f32 {
+        match self {
+            ArmorTier::Light => 100.0,
+            ArmorTier::Heavy => 200.0,
+        }
+    }
+}
+
+/// Kind of damage defines which resistance reduces it, whether armor absorbs it and which
+/// effects are shown when an actor is hit.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize)]
+pub enum DamageType {
+    Bullet,
+    Plasma,
+    Explosion,
+    Melee,
+    Environment,
+    Fall,
+}
+
+impl DamageType {
+    /// Armor does not protect from environmental hazards and falls.
+    pub fn bypasses_armor(self) -> bool {
+        matches!(self, DamageType::Environment | DamageType::Fall)
+    }
+}
+
+/// Multipliers of incoming damage per type of damage, 1.0 is normal damage and 0.0 is immunity.
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(default)]
+pub struct Resistances {
+    pub bullet: f32,
+    pub plasma: f32,
+    pub explosion: f32,
+    pub melee: f32,
+    pub environment: f32,
+    pub fall: f32,
+}
+
+impl Default for Resistances {
+    fn default() -> Self {
+        Self {
+            bullet: 1.0,
+            plasma: 1.0,
+            explosion: 1.0,
+            melee: 1.0,
+            environment: 1.0,
+            fall: 1.0,
+        }
+    }
+}
+
+impl Resistances {
+    pub fn multiplier(&self, damage_type: DamageType) -> f32 {
+        match damage_type {
+            DamageType::Bullet => self.bullet,
+            DamageType::Plasma => self.plasma,
+            DamageType::Explosion => self.explosion,
+            DamageType::Melee => self.melee,
+            DamageType::Environment => self.environment,
+            DamageType::Fall => self.fall,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if [
+            self.bullet,
+            self.plasma,
+            self.explosion,
+            self.melee,
+            self.environment,
+            self.fall,
+        ]
+        .iter()
+        .any(|&m| m < 0.0)
+        {
+            Err("resistances can't be negative".to_owned())
+        } else {
+            Ok(())
+        }
+    }
+}
+
 pub struct Character {
     pub pivot: Handle<Node>,
     pub body: RigidBodyHandle,
     pub health: f32,
     pub armor: f32,
+    pub armor_tier: ArmorTier,
     pub weapons: Vec<Handle<Weapon>>,
     pub current_weapon: u32,
     pub weapon_pivot: Handle<Node>,
+    pub inventory: Inventory,
+    /// Empty if hits land on the body of the character.
+    pub hitboxes: Vec<Hitbox>,
     pub sender: Option<Sender<Message>>,
 }
 
@@ -27,9 +160,12 @@ impl Default for Character {
             body: Default::default(),
             health: 100.0,
             armor: 100.0,
+            armor_tier: ArmorTier::Light,
             weapons: Vec::new(),
             current_weapon: 0,
             weapon_pivot: Handle::NONE,
+            inventory: Default::default(),
+            hitboxes: Default::default(),
             sender: None,
         }
     }
@@ -43,10 +179,30 @@ impl Visit for Character {
         self.body.visit("Body", visitor)?;
         self.health.visit("Health", visitor)?;
         self.armor.visit("Armor", visitor)?;
+
+        let mut armor_tier = self.armor_tier.id();
+        visit_since(&mut armor_tier, "ArmorTier", 11, visitor)?;
+        if visitor.is_reading() {
+            self.armor_tier = ArmorTier::from_id(armor_tier)?;
+            // Older saves let armor go below zero.
+            self.armor = self.armor.max(0.0);
+        }
         self.weapons.visit("Weapons", visitor)?;
         self.current_weapon.visit("CurrentWeapon", visitor)?;
         self.weapon_pivot.visit("WeaponPivot", visitor)?;
 
+        if visitor.is_reading() && read_version() < 10 {
+            // Keycards were stored in character before inventory was introduced.
+            let mut keycards = Vec::<u32>::new();
+            visit_since(&mut keycards, "Keycards", 5, visitor)?;
+            for id in keycards {
+                self.inventory.add_keycard(ItemKind::from_id(id)?);
+            }
+        } else {
+            self.inventory.visit("Inventory", visitor)?;
+        }
+        visit_since(&mut self.hitboxes, "Hitboxes", 14, visitor)?;
+
         visitor.leave_region()
     }
 }
@@ -95,15 +251,46 @@ impl Character {
             .vector
     }
 
-    pub fn damage(&mut self, amount: f32) {
+    pub fn get_armor_tier(&self) -> ArmorTier {
+        self.armor_tier
+    }
+
+    /// Applies damage, armor takes its part of the damage unless the damage bypasses armor.
+    pub fn damage(&mut self, amount: f32, bypass_armor: bool) {
         let amount = amount.abs();
-        if self.armor > 0.0 {
-            self.armor -= amount;
-            if self.armor < 0.0 {
-                self.health += self.armor;
-            }
+        let absorbed = if bypass_armor {
+            0.0
+        } else {
+            (amount * self.armor_tier.absorption()).min(self.armor)
+        };
+        self.armor -= absorbed;
+        self.health -= amount - absorbed;
+    }
+
+    /// Adds armor up to capacity of current tier, returns false if armor is already full.
+    pub fn add_armor(&mut self, amount: f32) -> bool {
+        let capacity = self.armor_tier.capacity();
+        if self.armor >= capacity {
+            false
         } else {
-            self.health -= amount;
+            self.armor = (self.armor + amount.abs()).min(capacity);
+            true
+        }
+    }
+
+    /// Raises tier of armor and sets armor to at least given amount, returns false if neither
+    /// tier nor armor has changed.
+    pub fn upgrade_armor(&mut self, tier: ArmorTier, amount: f32) -> bool {
+        let upgraded = tier.capacity() > self.armor_tier.capacity();
+        if upgraded {
+            self.armor_tier = tier;
+        }
+        let amount = amount.abs().min(self.armor_tier.capacity());
+        if self.armor < amount {
+            self.armor = amount;
+            true
+        } else {
+            upgraded
         }
     }
 
@@ -196,8 +383,17 @@ impl Character {
         }
     }
 
+    pub fn update_hitboxes(&self, scene: &mut Scene) {
+        for hitbox in self.hitboxes.iter() {
+            hitbox.update(scene);
+        }
+    }
+
     pub fn clean_up(&mut self, scene: &mut Scene) {
         scene.remove_node(self.pivot);
         scene.physics.remove_body(self.body);
+        for hitbox in self.hitboxes.iter() {
+            hitbox.clean_up(scene);
+        }
     }
 }
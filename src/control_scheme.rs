@@ -43,6 +43,9 @@ pub struct ControlScheme {
     pub run: ControlButtonDefinition,
     pub aim: ControlButtonDefinition,
     pub toss_grenade: ControlButtonDefinition,
+    pub interact: ControlButtonDefinition,
+    pub use_medkit: ControlButtonDefinition,
+    pub reload: ControlButtonDefinition,
     pub mouse_sens: f32,
     pub mouse_y_inverse: bool,
 }
@@ -94,6 +97,18 @@ impl Default for ControlScheme {
                 description: "Toss Grenade".to_string(),
                 button: ControlButton::Key(VirtualKeyCode::G),
             },
+            interact: ControlButtonDefinition {
+                description: "Use".to_string(),
+                button: ControlButton::Key(VirtualKeyCode::E),
+            },
+            use_medkit: ControlButtonDefinition {
+                description: "Use Medkit".to_string(),
+                button: ControlButton::Key(VirtualKeyCode::H),
+            },
+            reload: ControlButtonDefinition {
+                description: "Reload".to_string(),
+                button: ControlButton::Key(VirtualKeyCode::R),
+            },
             mouse_sens: 0.3,
             mouse_y_inverse: false,
         }
@@ -101,7 +116,7 @@ impl Default for ControlScheme {
 }
 
 impl ControlScheme {
-    pub fn buttons_mut(&mut self) -> [&mut ControlButtonDefinition; 11] {
+    pub fn buttons_mut(&mut self) -> [&mut ControlButtonDefinition; 14] {
         [
             &mut self.move_forward,
             &mut self.move_backward,
@@ -114,10 +129,13 @@ impl ControlScheme {
             &mut self.run,
             &mut self.aim,
             &mut self.toss_grenade,
+            &mut self.interact,
+            &mut self.use_medkit,
+            &mut self.reload,
         ]
     }
 
-    pub fn buttons(&self) -> [&ControlButtonDefinition; 11] {
+    pub fn buttons(&self) -> [&ControlButtonDefinition; 14] {
         [
             &self.move_forward,
             &self.move_backward,
@@ -130,6 +148,9 @@ impl ControlScheme {
             &self.run,
             &self.aim,
             &self.toss_grenade,
+            &self.interact,
+            &self.use_medkit,
+            &self.reload,
         ]
     }
 
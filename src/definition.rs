@@ -0,0 +1,336 @@
+//! Gameplay definitions are loaded from data files, so they can be added or tuned without
+//! recompiling the game. Every `.ron` file of a definition directory describes exactly one
+//! definition. Definitions are identified by numeric id, which is stored in saves, and by
+//! name, which is used by markers of level scenes.
+//!
+//! `DefinitionWatcher` reloads definitions when their files change, so combat can be tuned
+//! while the game is running.
+
+use crate::{
+    bot::BotDefinition,
+    inventory::AmmoDefinition,
+    item::ItemDefinition,
+    player::PlayerDefinition,
+    weapon::{projectile::ProjectileDefinition, WeaponDefinition},
+};
+use rg3d::{
+    engine::resource_manager::ResourceManager,
+    utils::log::{Log, MessageKind},
+};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+/// Interval in seconds between checks of definition files for changes.
+const WATCH_INTERVAL: f32 = 1.0;
+
+/// Loaded definitions of some type, `None` until they're loaded on first access.
+pub type Storage<D> = RwLock<Option<Arc<Registry<D>>>>;
+
+pub trait Definition: DeserializeOwned + Send + Sync + 'static {
+    /// Directory with definition files.
+    const PATH: &'static str;
+
+    fn id(&self) -> u32;
+
+    fn name(&self) -> &str;
+
+    fn storage() -> &'static Storage<Self>;
+
+    /// Checks values of a definition right after it was loaded.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Checks assets the definition refers to, so broken definition is reported when it is
+    /// loaded instead of when it is used.
+    fn check_assets(&self, _resource_manager: &ResourceManager) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct Registry<D> {
+    definitions: BTreeMap<u32, Arc<D>>,
+}
+
+impl<D> Default for Registry<D> {
+    fn default() -> Self {
+        Self {
+            definitions: Default::default(),
+        }
+    }
+}
+
+impl<D: Definition> Registry<D> {
+    /// Loads every definition file of given directory, ids and names must be unique.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        let paths = definition_files(dir).map_err(|e| {
+            format!(
+                "Unable to read definitions from {}. Reason: {}",
+                dir.display(),
+                e
+            )
+        })?;
+
+        let mut registry = Self::default();
+        for path in paths {
+            let file = File::open(&path).map_err(|e| {
+                format!(
+                    "Unable to open definition {}. Reason: {}",
+                    path.display(),
+                    e
+                )
+            })?;
+            let definition: D = ron::de::from_reader(file).map_err(|e| {
+                format!(
+                    "Unable to parse definition {}. Reason: {}",
+                    path.display(),
+                    e
+                )
+            })?;
+            definition
+                .validate()
+                .map_err(|e| format!("Invalid definition {}. Reason: {}", path.display(), e))?;
+
+            if registry.definitions.contains_key(&definition.id()) {
+                return Err(format!(
+                    "Definition {} has id {} which is already used!",
+                    path.display(),
+                    definition.id()
+                ));
+            }
+            if registry.find(definition.name()).is_some() {
+                return Err(format!(
+                    "Definition {} has name {} which is already used!",
+                    path.display(),
+                    definition.name()
+                ));
+            }
+
+            registry
+                .definitions
+                .insert(definition.id(), Arc::new(definition));
+        }
+
+        Ok(registry)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Arc<D>> {
+        self.definitions.get(&id)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Arc<D>> {
+        self.definitions.values().find(|d| d.name() == name)
+    }
+
+    /// Iterates over definitions in order of their ids.
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<D>> {
+        self.definitions.values()
+    }
+
+    fn check_assets(&self, resource_manager: &ResourceManager) -> Result<(), String> {
+        for definition in self.iter() {
+            definition.check_assets(resource_manager).map_err(|e| {
+                format!(
+                    "Invalid definition {} in {}. Reason: {}",
+                    definition.name(),
+                    D::PATH,
+                    e
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns sorted paths of definition files of a directory.
+fn definition_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "ron"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Loads definitions of given type from their directory, replacing previously loaded ones.
+pub fn load<D: Definition>(resource_manager: &ResourceManager) -> Result<(), String> {
+    let registry = Registry::<D>::load(D::PATH)?;
+    registry.check_assets(resource_manager)?;
+    *D::storage().write().unwrap() = Some(Arc::new(registry));
+    Ok(())
+}
+
+/// Loads definitions of every type and checks their assets and references between them. The
+/// game can't run without its definitions, so it must not start if this fails.
+pub fn load_all(resource_manager: &ResourceManager) -> Result<(), String> {
+    load::<ItemDefinition>(resource_manager)?;
+    load::<AmmoDefinition>(resource_manager)?;
+    load::<ProjectileDefinition>(resource_manager)?;
+    load::<WeaponDefinition>(resource_manager)?;
+    load::<BotDefinition>(resource_manager)?;
+    load::<PlayerDefinition>(resource_manager)?;
+    check_references()
+}
+
+/// Checks references between loaded definitions of different types.
+fn check_references() -> Result<(), String> {
+    let items = registry::<ItemDefinition>();
+    let ammo = registry::<AmmoDefinition>();
+    for definition in ammo.iter() {
+        if items.get(definition.item).is_none() {
+            return Err(format!(
+                "Ammo {} is given by item {} which is not defined!",
+                definition.name, definition.item
+            ));
+        }
+    }
+    for definition in registry::<WeaponDefinition>().iter() {
+        if items.get(definition.item).is_none() {
+            return Err(format!(
+                "Weapon {} is given by item {} which is not defined!",
+                definition.name, definition.item
+            ));
+        }
+        if ammo.get(definition.ammo_kind.id()).is_none() {
+            return Err(format!(
+                "Weapon {} uses ammo {} which is not defined!",
+                definition.name,
+                definition.ammo_kind.id()
+            ));
+        }
+    }
+    if registry::<PlayerDefinition>().get(0).is_none() {
+        return Err(format!(
+            "Player definition with id 0 is not defined in {}!",
+            PlayerDefinition::PATH
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns loaded definitions of given type, they're loaded on first access. If loading has
+/// failed, the error is logged and there are no definitions of the type.
+pub fn registry<D: Definition>() -> Arc<Registry<D>> {
+    if let Some(registry) = D::storage().read().unwrap().as_ref() {
+        return registry.clone();
+    }
+
+    let mut storage = D::storage().write().unwrap();
+    storage
+        .get_or_insert_with(|| {
+            Arc::new(Registry::load(D::PATH).unwrap_or_else(|e| {
+                Log::writeln(MessageKind::Error, e);
+                Default::default()
+            }))
+        })
+        .clone()
+}
+
+/// Loads definitions of given type again. Definitions can be changed or added, but not removed,
+/// because live entities and saves refer to them by id. Previous definitions are kept if new
+/// ones can't be loaded.
+pub fn reload<D: Definition>(resource_manager: &ResourceManager) -> Result<(), String> {
+    let new = Registry::<D>::load(D::PATH)?;
+    new.check_assets(resource_manager)?;
+    let old = registry::<D>();
+    if let Some(removed) = old.iter().find(|d| new.get(d.id()).is_none()) {
+        return Err(format!(
+            "Definition {} of {} can't be removed while the game is running!",
+            removed.name(),
+            D::PATH
+        ));
+    }
+    *D::storage().write().unwrap() = Some(Arc::new(new));
+
+    // New definitions can break references of other definitions to them.
+    if let Err(e) = check_references() {
+        *D::storage().write().unwrap() = Some(old);
+        return Err(format!(
+            "Definitions of {} are not reloaded. Reason: {}",
+            D::PATH,
+            e
+        ));
+    }
+    Ok(())
+}
+
+struct WatchedDirectory {
+    path: &'static str,
+    /// Modification times of definition files at the moment of last check.
+    snapshot: HashMap<PathBuf, SystemTime>,
+    reload: fn(&ResourceManager) -> Result<(), String>,
+}
+
+fn snapshot(dir: &str) -> HashMap<PathBuf, SystemTime> {
+    definition_files(Path::new(dir))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+
+/// Polls modification times of definition files and reloads definitions of a directory when
+/// any of its files was changed, added or removed.
+#[derive(Default)]
+pub struct DefinitionWatcher {
+    directories: Vec<WatchedDirectory>,
+    timer: f32,
+}
+
+impl DefinitionWatcher {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn watch<D: Definition>(mut self) -> Self {
+        self.directories.push(WatchedDirectory {
+            path: D::PATH,
+            snapshot: snapshot(D::PATH),
+            reload: reload::<D>,
+        });
+        self
+    }
+
+    /// Returns true if any definitions were reloaded, live entities should pick them up then.
+    pub fn update(&mut self, dt: f32, resource_manager: &ResourceManager) -> bool {
+        self.timer -= dt;
+        if self.timer > 0.0 {
+            return false;
+        }
+        self.timer = WATCH_INTERVAL;
+
+        let mut reloaded = false;
+        for directory in self.directories.iter_mut() {
+            let snapshot = snapshot(directory.path);
+            if snapshot == directory.snapshot {
+                continue;
+            }
+            // Broken file is not retried until it is changed again.
+            directory.snapshot = snapshot;
+
+            match (directory.reload)(resource_manager) {
+                Ok(()) => {
+                    Log::writeln(
+                        MessageKind::Information,
+                        format!("Definitions from {} were reloaded.", directory.path),
+                    );
+                    reloaded = true;
+                }
+                Err(e) => Log::writeln(MessageKind::Error, e),
+            }
+        }
+        reloaded
+    }
+}
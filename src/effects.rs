@@ -15,13 +15,16 @@ use rg3d::{
         transform::TransformBuilder,
     },
 };
+use serde::Deserialize;
 use std::path::Path;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Deserialize)]
 pub enum EffectKind {
     BulletImpact,
     BloodSpray,
     Smoke,
+    PlasmaBurn,
+    Headshot,
 }
 
 pub fn create(
@@ -35,6 +38,8 @@ pub fn create(
         EffectKind::BulletImpact => create_bullet_impact(graph, resource_manager, pos, orientation),
         EffectKind::BloodSpray => create_blood_spray(graph, resource_manager, pos, orientation),
         EffectKind::Smoke => create_smoke(graph, resource_manager, pos, orientation),
+        EffectKind::PlasmaBurn => create_plasma_burn(graph, resource_manager, pos, orientation),
+        EffectKind::Headshot => create_headshot(graph, resource_manager, pos, orientation),
     }
 }
 
@@ -117,6 +122,91 @@ fn create_blood_spray(
     .build(graph)
 }
 
+fn create_plasma_burn(
+    graph: &mut Graph,
+    resource_manager: ResourceManager,
+    pos: Vector3<f32>,
+    orientation: UnitQuaternion<f32>,
+) -> Handle<Node> {
+    ParticleSystemBuilder::new(
+        BaseBuilder::new().with_lifetime(1.0).with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(pos)
+                .with_local_rotation(orientation)
+                .build(),
+        ),
+    )
+    .with_acceleration(Vector3::new(0.0, 0.0, 0.0))
+    .with_color_over_lifetime_gradient({
+        let mut gradient = ColorGradient::new();
+        gradient.add_point(GradientPoint::new(0.00, Color::from_rgba(200, 255, 255, 0)));
+        gradient.add_point(GradientPoint::new(
+            0.05,
+            Color::from_rgba(80, 200, 255, 255),
+        ));
+        gradient.add_point(GradientPoint::new(
+            0.95,
+            Color::from_rgba(40, 120, 255, 255),
+        ));
+        gradient.add_point(GradientPoint::new(1.00, Color::from_rgba(40, 60, 255, 0)));
+        gradient
+    })
+    .with_emitters(vec![SphereEmitterBuilder::new(
+        BaseEmitterBuilder::new()
+            .with_max_particles(150)
+            .with_spawn_rate(800)
+            .with_size_modifier_range(NumericRange::new(-0.01, -0.0125))
+            .with_size_range(NumericRange::new(0.02, 0.04))
+            .with_x_velocity_range(NumericRange::new(-0.01, 0.01))
+            .with_y_velocity_range(NumericRange::new(0.01, 0.02))
+            .with_z_velocity_range(NumericRange::new(-0.01, 0.01))
+            .resurrect_particles(false),
+    )
+    .with_radius(0.01)
+    .build()])
+    .with_texture(resource_manager.request_texture(Path::new("data/particles/circle_05.png")))
+    .build(graph)
+}
+
+fn create_headshot(
+    graph: &mut Graph,
+    resource_manager: ResourceManager,
+    pos: Vector3<f32>,
+    orientation: UnitQuaternion<f32>,
+) -> Handle<Node> {
+    ParticleSystemBuilder::new(
+        BaseBuilder::new().with_lifetime(1.5).with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(pos)
+                .with_local_rotation(orientation)
+                .build(),
+        ),
+    )
+    .with_acceleration(Vector3::new(0.0, -10.0, 0.0))
+    .with_color_over_lifetime_gradient({
+        let mut gradient = ColorGradient::new();
+        gradient.add_point(GradientPoint::new(0.00, Color::from_rgba(200, 0, 0, 255)));
+        gradient.add_point(GradientPoint::new(0.90, Color::from_rgba(120, 0, 0, 255)));
+        gradient.add_point(GradientPoint::new(1.00, Color::from_rgba(120, 0, 0, 0)));
+        gradient
+    })
+    .with_emitters(vec![SphereEmitterBuilder::new(
+        BaseEmitterBuilder::new()
+            .with_max_particles(400)
+            .with_spawn_rate(4000)
+            .with_size_modifier_range(NumericRange::new(-0.01, -0.0125))
+            .with_size_range(NumericRange::new(0.02, 0.04))
+            .with_x_velocity_range(NumericRange::new(-0.02, 0.02))
+            .with_y_velocity_range(NumericRange::new(0.01, 0.03))
+            .with_z_velocity_range(NumericRange::new(-0.02, 0.02))
+            .resurrect_particles(false),
+    )
+    .with_radius(0.03)
+    .build()])
+    .with_texture(resource_manager.request_texture(Path::new("data/particles/dirt_01.png")))
+    .build(graph)
+}
+
 fn create_smoke(
     graph: &mut Graph,
     resource_manager: ResourceManager,
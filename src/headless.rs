@@ -0,0 +1,133 @@
+//! Headless simulation runs a level without window, renderer or sound output. It owns the
+//! scene directly instead of putting it into the engine, so it can be stepped from tests
+//! or bots with a fixed time step and then inspected.
+
+use crate::{
+    actor::ActorContainer,
+    control_scheme::ControlScheme,
+    definition,
+    level::{manifest::LevelDefinition, stats::Outcome, Level},
+    message::Message,
+    GameTime, FIXED_FPS,
+};
+use rg3d::{core::algebra::Vector2, engine::resource_manager::ResourceManager, scene::Scene};
+use std::sync::{
+    mpsc::{self, Receiver, Sender},
+    Arc, RwLock,
+};
+
+pub struct HeadlessSimulation {
+    pub level: Level,
+    pub scene: Scene,
+    resource_manager: ResourceManager,
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    time: GameTime,
+    tick: u64,
+    outcome: Option<Outcome>,
+}
+
+impl HeadlessSimulation {
+    /// Loads game definitions and the level, fails if definitions can't be loaded.
+    pub async fn new(definition: LevelDefinition, seed: u64) -> Result<Self, String> {
+        let resource_manager = ResourceManager::new();
+        definition::load_all(&resource_manager)
+            .map_err(|e| format!("Unable to load game definitions. Reason: {}", e))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let control_scheme = Arc::new(RwLock::new(ControlScheme::default()));
+
+        let (level, scene) = Level::new(
+            definition,
+            seed,
+            resource_manager.clone(),
+            control_scheme,
+            sender.clone(),
+        )
+        .await;
+
+        Ok(Self {
+            level,
+            scene,
+            resource_manager,
+            sender,
+            receiver,
+            time: GameTime::new(1.0 / FIXED_FPS),
+            tick: 0,
+            outcome: None,
+        })
+    }
+
+    /// Performs exactly one fixed step of the simulation: updates scene (physics, animations),
+    /// level and then handles every message emitted during the step.
+    pub fn step(&mut self) {
+        self.tick += 1;
+        self.time.elapsed += self.time.delta as f64;
+
+        // There is no real frame, so any sane frame size is fine.
+        self.scene
+            .update(Vector2::new(1280.0, 720.0), self.time.delta);
+        self.level.update(&mut self.scene, self.time);
+
+        while let Ok(message) = self.receiver.try_recv() {
+            if let &Message::EndMatch { outcome } = &message {
+                self.outcome = Some(outcome);
+            }
+
+            rg3d::futures::executor::block_on(self.level.handle_message(
+                &mut self.scene,
+                self.resource_manager.clone(),
+                &message,
+            ));
+        }
+    }
+
+    /// Performs given amount of fixed steps.
+    pub fn run(&mut self, steps: u64) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Sends a message to the level, it will be handled on next step.
+    pub fn send(&self, message: Message) {
+        self.sender.send(message).unwrap();
+    }
+
+    pub fn sender(&self) -> Sender<Message> {
+        self.sender.clone()
+    }
+
+    pub fn actors(&self) -> &ActorContainer {
+        self.level.actors()
+    }
+
+    pub fn projectile_count(&self) -> usize {
+        self.level.projectiles().count()
+    }
+
+    /// Amount of items that can be picked up, picked up constant items are inactive until
+    /// they respawn.
+    pub fn item_count(&self) -> usize {
+        self.level.items().iter().filter(|i| i.is_active()).count()
+    }
+
+    pub fn time(&self) -> GameTime {
+        self.time
+    }
+
+    /// Amount of fixed steps performed so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns true if level has requested end of the match (player died or reached level exit).
+    pub fn is_match_ended(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// Returns outcome of the match if it has ended.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+}
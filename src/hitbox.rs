@@ -0,0 +1,212 @@
+//! Hitboxes are sensor colliders that follow bones of a character, so shots hit the limb
+//! they're aimed at instead of a single capsule of the character. Every hitbox has its own
+//! damage multiplier, which makes headshots deadly and shots to limbs weak.
+
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Translation3, UnitQuaternion, Vector3},
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    physics::{
+        dynamics::{BodyStatus, RigidBodyBuilder},
+        geometry::ColliderBuilder,
+    },
+    scene::{graph::Graph, node::Node, RigidBodyHandle, Scene},
+    utils::log::{Log, MessageKind},
+};
+use serde::Deserialize;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum HitLocation {
+    Head,
+    Torso,
+    Arm,
+    Leg,
+}
+
+impl Default for HitLocation {
+    fn default() -> Self {
+        Self::Torso
+    }
+}
+
+impl HitLocation {
+    pub fn id(self) -> u32 {
+        match self {
+            HitLocation::Head => 0,
+            HitLocation::Torso => 1,
+            HitLocation::Arm => 2,
+            HitLocation::Leg => 3,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(HitLocation::Head),
+            1 => Ok(HitLocation::Torso),
+            2 => Ok(HitLocation::Arm),
+            3 => Ok(HitLocation::Leg),
+            _ => Err(format!("Invalid hit location {}", id)),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct HitboxDefinition {
+    pub location: HitLocation,
+    /// Bone the hitbox is attached to.
+    pub bone: String,
+    /// Hitbox is a capsule between `bone` and this bone, or a sphere around `bone` if empty.
+    #[serde(default)]
+    pub end_bone: String,
+    pub radius: f32,
+    /// Damage of a hit to the hitbox is multiplied by this value.
+    pub damage_multiplier: f32,
+}
+
+/// Checks hitboxes of a definition right after it was loaded.
+pub fn validate(definitions: &[HitboxDefinition]) -> Result<(), String> {
+    for definition in definitions {
+        if definition.bone.is_empty() {
+            return Err("bone of a hitbox is not set".to_owned());
+        }
+        if definition.radius <= 0.0 || definition.damage_multiplier < 0.0 {
+            return Err(format!(
+                "hitbox on bone {} must have positive radius and non-negative damage multiplier",
+                definition.bone
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Hitbox {
+    pub location: HitLocation,
+    pub damage_multiplier: f32,
+    /// Index of definition of the hitbox in definition of its character.
+    index: u32,
+    bone: Handle<Node>,
+    end_bone: Handle<Node>,
+    body: RigidBodyHandle,
+}
+
+impl Visit for Hitbox {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut location = self.location.id();
+        location.visit("Location", visitor)?;
+        if visitor.is_reading() {
+            self.location = HitLocation::from_id(location)?;
+        }
+        self.damage_multiplier.visit("DamageMultiplier", visitor)?;
+        self.index.visit("Index", visitor)?;
+        self.bone.visit("Bone", visitor)?;
+        self.end_bone.visit("EndBone", visitor)?;
+        self.body.visit("Body", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl Hitbox {
+    pub fn body(&self) -> RigidBodyHandle {
+        self.body
+    }
+
+    /// Capsule hitbox lies along Y axis of its body, so the body is rotated towards end bone.
+    fn pose(&self, graph: &Graph) -> Isometry3<f32> {
+        let begin = graph[self.bone].global_position();
+        if self.end_bone.is_some() {
+            let end = graph[self.end_bone].global_position();
+            let rotation = UnitQuaternion::rotation_between(&Vector3::y(), &(end - begin))
+                .unwrap_or_else(UnitQuaternion::identity);
+            Isometry3::from_parts(Translation3::from((begin + end).scale(0.5)), rotation)
+        } else {
+            Isometry3::translation(begin.x, begin.y, begin.z)
+        }
+    }
+
+    /// Moves the hitbox to its bones.
+    pub fn update(&self, scene: &mut Scene) {
+        let pose = self.pose(&scene.graph);
+        if let Some(body) = scene.physics.bodies.get_mut(self.body.into()) {
+            body.set_next_kinematic_position(pose);
+        }
+    }
+
+    pub fn clean_up(&self, scene: &mut Scene) {
+        scene.physics.remove_body(self.body);
+    }
+}
+
+/// Creates hitboxes on bones of a model, hitboxes with missing bones are skipped.
+pub fn create_hitboxes(
+    definitions: &[HitboxDefinition],
+    model: Handle<Node>,
+    scene: &mut Scene,
+) -> Vec<Hitbox> {
+    // Length of capsules is taken from bind pose of the model.
+    scene.graph.update_hierarchical_data();
+
+    let mut hitboxes = Vec::new();
+    for (index, definition) in definitions.iter().enumerate() {
+        let bone = scene.graph.find_by_name(model, &definition.bone);
+        let end_bone = if definition.end_bone.is_empty() {
+            Handle::NONE
+        } else {
+            scene.graph.find_by_name(model, &definition.end_bone)
+        };
+        if bone.is_none() || (!definition.end_bone.is_empty() && end_bone.is_none()) {
+            Log::writeln(
+                MessageKind::Warning,
+                format!(
+                    "Hitbox on bone {} is skipped, its bones are missing in the model!",
+                    definition.bone
+                ),
+            );
+            continue;
+        }
+
+        let collider = if end_bone.is_some() {
+            let length = scene.graph[bone]
+                .global_position()
+                .metric_distance(&scene.graph[end_bone].global_position());
+            ColliderBuilder::capsule_y(length * 0.5, definition.radius)
+        } else {
+            ColliderBuilder::ball(definition.radius)
+        };
+
+        let mut hitbox = Hitbox {
+            location: definition.location,
+            damage_multiplier: definition.damage_multiplier,
+            index: index as u32,
+            bone,
+            end_bone,
+            body: Default::default(),
+        };
+        hitbox.body = scene.physics.add_body(
+            RigidBodyBuilder::new(BodyStatus::Kinematic)
+                .position(hitbox.pose(&scene.graph))
+                .build(),
+        );
+        // Sensor does not push anything, it is only found by ray casts.
+        scene
+            .physics
+            .add_collider(collider.sensor(true).build(), hitbox.body);
+
+        hitboxes.push(hitbox);
+    }
+    hitboxes
+}
+
+/// Picks up damage multipliers of reloaded definitions, hitboxes themselves stay the same.
+pub fn refresh_hitboxes(hitboxes: &mut [Hitbox], definitions: &[HitboxDefinition]) {
+    for hitbox in hitboxes.iter_mut() {
+        if let Some(definition) = definitions.get(hitbox.index as usize) {
+            hitbox.damage_multiplier = definition.damage_multiplier;
+        }
+    }
+}
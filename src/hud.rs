@@ -1,4 +1,8 @@
-use crate::{GameEngine, Gui, UINodeHandle};
+use crate::{
+    inventory::{Inventory, MAX_GRENADES, MAX_MEDKITS},
+    weapon::Weapon,
+    GameEngine, Gui, UINodeHandle,
+};
 use rg3d::{
     core::color::Color,
     event::{Event, WindowEvent},
@@ -22,12 +26,37 @@ use std::{
 
 pub struct Hud {
     root: UINodeHandle,
+    crosshair: UINodeHandle,
+    /// Size of crosshair in pixels, crosshair is resized only when the size changes.
+    crosshair_size: f32,
     health: UINodeHandle,
     armor: UINodeHandle,
     ammo: UINodeHandle,
+    ammo_warning: UINodeHandle,
+    ammo_warning_text: String,
     died: UINodeHandle,
+    message: UINodeHandle,
+    /// Time in seconds until current message will be hidden.
+    message_timeout: f32,
+    objectives: UINodeHandle,
+    /// Last text of objectives panel, panel is updated only when the text changes.
+    objectives_text: String,
+    inventory: UINodeHandle,
+    inventory_text: String,
 }
 
+/// Size of crosshair in pixels without spread.
+const CROSSHAIR_SIZE: f32 = 33.0;
+
+/// Pixels that every degree of spread adds to size of crosshair.
+const CROSSHAIR_PIXELS_PER_DEGREE: f32 = 10.0;
+
+/// Magazine is considered low when this part of it is left.
+const LOW_AMMO_FRACTION: f32 = 0.25;
+
+/// How long a message stays on screen.
+const MESSAGE_DURATION: f32 = 4.0;
+
 impl Hud {
     pub fn new(engine: &mut GameEngine) -> Self {
         let frame_size = engine.renderer.get_frame_size();
@@ -42,30 +71,44 @@ impl Hud {
         .unwrap();
         let font = SharedFont(Arc::new(Mutex::new(font)));
 
+        let small_font = Font::from_file(
+            Path::new("data/ui/SquaresBold.ttf"),
+            20.0,
+            Font::default_char_set(),
+        )
+        .unwrap();
+        let small_font = SharedFont(Arc::new(Mutex::new(small_font)));
+
+        let crosshair;
         let health;
         let armor;
         let ammo;
+        let ammo_warning;
         let died;
+        let message;
+        let objectives;
+        let inventory;
         let root = GridBuilder::new(
             WidgetBuilder::new()
                 .with_width(frame_size.0 as f32)
                 .with_height(frame_size.1 as f32)
                 .with_visibility(false)
-                .with_child(
-                    ImageBuilder::new(
+                .with_child({
+                    crosshair = ImageBuilder::new(
                         WidgetBuilder::new()
                             .with_horizontal_alignment(HorizontalAlignment::Center)
                             .with_vertical_alignment(VerticalAlignment::Center)
-                            .with_width(33.0)
-                            .with_height(33.0)
+                            .with_width(CROSSHAIR_SIZE)
+                            .with_height(CROSSHAIR_SIZE)
                             .on_row(0)
                             .on_column(1),
                     )
                     .with_texture(utils::into_gui_texture(
                         resource_manager.request_texture(Path::new("data/ui/crosshair.tga")),
                     ))
-                    .build(ctx),
-                )
+                    .build(ctx);
+                    crosshair
+                })
                 .with_child(
                     GridBuilder::new(
                         WidgetBuilder::new()
@@ -208,6 +251,20 @@ impl Hud {
                     .with_orientation(Orientation::Horizontal)
                     .build(ctx),
                 )
+                .with_child({
+                    ammo_warning = TextBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(0)
+                            .on_column(0)
+                            .with_margin(Thickness::bottom(50.0))
+                            .with_foreground(Brush::Solid(Color::opaque(200, 0, 0)))
+                            .with_vertical_alignment(VerticalAlignment::Bottom)
+                            .with_horizontal_alignment(HorizontalAlignment::Center),
+                    )
+                    .with_font(small_font.clone())
+                    .build(ctx);
+                    ammo_warning
+                })
                 .with_child({
                     died = TextBuilder::new(
                         WidgetBuilder::new()
@@ -218,10 +275,50 @@ impl Hud {
                             .with_vertical_alignment(VerticalAlignment::Center)
                             .with_horizontal_alignment(HorizontalAlignment::Center),
                     )
-                    .with_font(font)
+                    .with_font(font.clone())
                     .with_text("You Died")
                     .build(ctx);
                     died
+                })
+                .with_child({
+                    message = TextBuilder::new(
+                        WidgetBuilder::new()
+                            .with_visibility(false)
+                            .on_row(0)
+                            .on_column(1)
+                            .with_margin(Thickness::top(100.0))
+                            .with_vertical_alignment(VerticalAlignment::Top)
+                            .with_horizontal_alignment(HorizontalAlignment::Center),
+                    )
+                    .with_font(font)
+                    .build(ctx);
+                    message
+                })
+                .with_child({
+                    objectives = TextBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(0)
+                            .on_column(0)
+                            .with_margin(Thickness::uniform(20.0))
+                            .with_vertical_alignment(VerticalAlignment::Top)
+                            .with_horizontal_alignment(HorizontalAlignment::Left),
+                    )
+                    .with_font(small_font.clone())
+                    .build(ctx);
+                    objectives
+                })
+                .with_child({
+                    inventory = TextBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(0)
+                            .on_column(2)
+                            .with_margin(Thickness::uniform(20.0))
+                            .with_vertical_alignment(VerticalAlignment::Top)
+                            .with_horizontal_alignment(HorizontalAlignment::Right),
+                    )
+                    .with_font(small_font)
+                    .build(ctx);
+                    inventory
                 }),
         )
         .add_column(Column::stretch())
@@ -232,10 +329,20 @@ impl Hud {
 
         Self {
             root,
+            crosshair,
+            crosshair_size: CROSSHAIR_SIZE,
             health,
             armor,
             ammo,
+            ammo_warning,
+            ammo_warning_text: Default::default(),
             died,
+            message,
+            message_timeout: 0.0,
+            objectives,
+            objectives_text: Default::default(),
+            inventory,
+            inventory_text: Default::default(),
         }
     }
 
@@ -243,7 +350,7 @@ impl Hud {
         ui.send_message(TextMessage::text(
             self.health,
             MessageDirection::ToWidget,
-            format!("{}", health),
+            format!("{:.0}", health),
         ));
     }
 
@@ -251,16 +358,75 @@ impl Hud {
         ui.send_message(TextMessage::text(
             self.armor,
             MessageDirection::ToWidget,
-            format!("{}", armor),
+            format!("{:.0}", armor),
         ));
     }
 
-    pub fn set_ammo(&mut self, ui: &mut Gui, ammo: u32) {
+    /// Shows ammo in magazine of a weapon and ammo in reserve, warns when ammo is low.
+    pub fn set_ammo(&mut self, ui: &mut Gui, weapon: &Weapon, reserve: u32) {
         ui.send_message(TextMessage::text(
             self.ammo,
             MessageDirection::ToWidget,
-            format!("{}", ammo),
+            format!("{}/{}", weapon.ammo(), reserve),
         ));
+
+        let warning = if weapon.is_reloading() {
+            "Reloading"
+        } else if weapon.ammo() == 0 && reserve == 0 {
+            "No Ammo"
+        } else if (weapon.ammo() as f32) <= weapon.magazine_size() as f32 * LOW_AMMO_FRACTION {
+            "Low Ammo"
+        } else {
+            ""
+        };
+        if self.ammo_warning_text != warning {
+            ui.send_message(TextMessage::text(
+                self.ammo_warning,
+                MessageDirection::ToWidget,
+                warning.to_owned(),
+            ));
+            self.ammo_warning_text = warning.to_owned();
+        }
+    }
+
+    /// Expands crosshair with spread of current weapon in degrees.
+    pub fn set_spread(&mut self, ui: &mut Gui, spread: f32) {
+        let size = (CROSSHAIR_SIZE + spread * CROSSHAIR_PIXELS_PER_DEGREE).round();
+        if (self.crosshair_size - size).abs() >= 1.0 {
+            ui.send_message(WidgetMessage::width(
+                self.crosshair,
+                MessageDirection::ToWidget,
+                size,
+            ));
+            ui.send_message(WidgetMessage::height(
+                self.crosshair,
+                MessageDirection::ToWidget,
+                size,
+            ));
+            self.crosshair_size = size;
+        }
+    }
+
+    pub fn set_inventory(&mut self, ui: &mut Gui, inventory: &Inventory) {
+        let mut text = format!(
+            "Grenades: {}/{}\nMedkits: {}/{}",
+            inventory.grenades(),
+            MAX_GRENADES,
+            inventory.medkits(),
+            MAX_MEDKITS
+        );
+        for keycard in inventory.keycards() {
+            text += "\n";
+            text += keycard.description();
+        }
+        if self.inventory_text != text {
+            ui.send_message(TextMessage::text(
+                self.inventory,
+                MessageDirection::ToWidget,
+                text.clone(),
+            ));
+            self.inventory_text = text;
+        }
     }
 
     pub fn set_visible(&mut self, ui: &mut Gui, visible: bool) {
@@ -279,6 +445,45 @@ impl Hud {
         ));
     }
 
+    /// Shows a message at the top of the screen, it replaces previous message.
+    pub fn show_message(&mut self, ui: &mut Gui, text: String) {
+        ui.send_message(TextMessage::text(
+            self.message,
+            MessageDirection::ToWidget,
+            text,
+        ));
+        ui.send_message(WidgetMessage::visibility(
+            self.message,
+            MessageDirection::ToWidget,
+            true,
+        ));
+        self.message_timeout = MESSAGE_DURATION;
+    }
+
+    pub fn set_objectives(&mut self, ui: &mut Gui, text: String) {
+        if self.objectives_text != text {
+            ui.send_message(TextMessage::text(
+                self.objectives,
+                MessageDirection::ToWidget,
+                text.clone(),
+            ));
+            self.objectives_text = text;
+        }
+    }
+
+    pub fn update(&mut self, ui: &mut Gui, dt: f32) {
+        if self.message_timeout > 0.0 {
+            self.message_timeout -= dt;
+            if self.message_timeout <= 0.0 {
+                ui.send_message(WidgetMessage::visibility(
+                    self.message,
+                    MessageDirection::ToWidget,
+                    false,
+                ));
+            }
+        }
+    }
+
     pub fn process_event(&mut self, engine: &mut GameEngine, event: &Event<()>) {
         if let Event::WindowEvent { event, .. } = event {
             if let WindowEvent::Resized(new_size) = event {
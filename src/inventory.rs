@@ -0,0 +1,217 @@
+//! Inventory holds consumables of a character: grenades, medkits carried for later use,
+//! keycards and reserves of ammo. Every kind of consumable has a capacity, items that don't
+//! fit into inventory are left where they are.
+
+use crate::{
+    definition::{self, Definition, Storage},
+    item::ItemKind,
+};
+use rg3d::core::visitor::{Visit, VisitResult, Visitor};
+use serde::Deserialize;
+use std::sync::RwLock;
+
+pub const MAX_GRENADES: u32 = 5;
+pub const MAX_MEDKITS: usize = 3;
+
+/// Amount of health restored by a medkit without amount override.
+pub const MEDKIT_HEALTH: u32 = 20;
+
+/// Id of an ammo definition, definitions are loaded from `data/ammo`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct AmmoKind(u32);
+
+impl AmmoKind {
+    pub fn id(self) -> u32 {
+        self.0
+    }
+
+    /// Returns kind of ammo an ammo item gives.
+    pub fn from_item(item: ItemKind) -> Option<Self> {
+        definition::registry::<AmmoDefinition>()
+            .iter()
+            .find(|d| d.item == item.id())
+            .map(|d| AmmoKind(d.id))
+    }
+
+    /// Max amount of ammo of this kind a character can carry in reserve.
+    pub fn capacity(self) -> u32 {
+        definition::registry::<AmmoDefinition>()
+            .get(self.0)
+            .map_or(0, |d| d.capacity)
+    }
+
+    /// Amount of ammo an ammo item gives without amount override.
+    pub fn pickup_amount(self) -> u32 {
+        definition::registry::<AmmoDefinition>()
+            .get(self.0)
+            .map_or(0, |d| d.pickup_amount)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AmmoDefinition {
+    /// Unique id of the ammo, reserves of ammo in saves are indexed by it.
+    pub id: u32,
+    pub name: String,
+    /// Id of item definition which gives the ammo when picked up.
+    pub item: u32,
+    pub capacity: u32,
+    pub pickup_amount: u32,
+}
+
+static DEFINITIONS: Storage<AmmoDefinition> = RwLock::new(None);
+
+impl Definition for AmmoDefinition {
+    const PATH: &'static str = "data/ammo";
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn storage() -> &'static Storage<Self> {
+        &DEFINITIONS
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        ItemKind::from_id(self.item).map(|_| ())
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Inventory {
+    grenades: u32,
+    /// Health each of carried medkits restores.
+    medkits: Vec<u32>,
+    keycards: Vec<ItemKind>,
+    /// Reserves of ammo, indexed by id of ammo kind.
+    ammo: Vec<u32>,
+}
+
+impl Visit for Inventory {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.grenades.visit("Grenades", visitor)?;
+        self.medkits.visit("Medkits", visitor)?;
+
+        let mut keycards = self.keycards.iter().map(|k| k.id()).collect::<Vec<u32>>();
+        keycards.visit("Keycards", visitor)?;
+        if visitor.is_reading() {
+            self.keycards = keycards
+                .into_iter()
+                .map(ItemKind::from_id)
+                .collect::<Result<_, _>>()?;
+        }
+
+        self.ammo.visit("Ammo", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl Inventory {
+    pub fn grenades(&self) -> u32 {
+        self.grenades
+    }
+
+    /// Adds grenades up to capacity, returns false if there was no room for them.
+    pub fn add_grenades(&mut self, amount: u32) -> bool {
+        if self.grenades >= MAX_GRENADES {
+            false
+        } else {
+            self.grenades = (self.grenades + amount).min(MAX_GRENADES);
+            true
+        }
+    }
+
+    pub fn take_grenade(&mut self) -> bool {
+        if self.grenades > 0 {
+            self.grenades -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn medkits(&self) -> usize {
+        self.medkits.len()
+    }
+
+    /// Puts a medkit that restores given amount of health, returns false if there is no room.
+    pub fn add_medkit(&mut self, health: u32) -> bool {
+        if self.medkits.len() >= MAX_MEDKITS {
+            false
+        } else {
+            self.medkits.push(health);
+            true
+        }
+    }
+
+    /// Takes first carried medkit and returns amount of health it restores.
+    pub fn take_medkit(&mut self) -> Option<u32> {
+        if self.medkits.is_empty() {
+            None
+        } else {
+            Some(self.medkits.remove(0))
+        }
+    }
+
+    pub fn keycards(&self) -> &[ItemKind] {
+        &self.keycards
+    }
+
+    pub fn has_keycard(&self, keycard: ItemKind) -> bool {
+        self.keycards.contains(&keycard)
+    }
+
+    /// Adds keycard, returns false if the keycard is already in the inventory.
+    pub fn add_keycard(&mut self, keycard: ItemKind) -> bool {
+        if self.has_keycard(keycard) {
+            false
+        } else {
+            self.keycards.push(keycard);
+            true
+        }
+    }
+
+    pub fn ammo(&self, kind: AmmoKind) -> u32 {
+        self.ammo.get(kind.id() as usize).copied().unwrap_or(0)
+    }
+
+    /// Returns ammo of every kind in reserve.
+    pub fn total_ammo(&self) -> u32 {
+        self.ammo.iter().sum()
+    }
+
+    /// Adds ammo up to capacity of the kind, returns false if reserve is already full.
+    pub fn add_ammo(&mut self, kind: AmmoKind, amount: u32) -> bool {
+        let index = kind.id() as usize;
+        if self.ammo.len() <= index {
+            self.ammo.resize(index + 1, 0);
+        }
+        let reserve = &mut self.ammo[index];
+        if *reserve >= kind.capacity() {
+            false
+        } else {
+            *reserve = (*reserve + amount).min(kind.capacity());
+            true
+        }
+    }
+
+    /// Takes up to given amount of ammo from reserve, returns amount that was taken.
+    pub fn take_ammo(&mut self, kind: AmmoKind, amount: u32) -> u32 {
+        match self.ammo.get_mut(kind.id() as usize) {
+            Some(reserve) => {
+                let taken = amount.min(*reserve);
+                *reserve -= taken;
+                taken
+            }
+            None => 0,
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use crate::message::Message;
+use crate::{
+    definition::{self, Definition, Storage},
+    message::Message,
+    save::visit_since,
+};
 use rg3d::{
     core::{
         algebra::Vector3,
@@ -8,10 +12,18 @@ use rg3d::{
     engine::resource_manager::ResourceManager,
     scene::{base::BaseBuilder, graph::Graph, node::Node, transform::TransformBuilder, Scene},
     sound::pool::PoolIteratorMut,
+    utils::log::{Log, MessageKind},
+};
+use serde::Deserialize;
+use std::{
+    path::Path,
+    sync::{mpsc::Sender, Arc, RwLock},
 };
-use std::{path::Path, sync::mpsc::Sender};
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// Time in seconds after which temporary item (dropped by a dead actor) disappears.
+pub const TEMPORARY_ITEM_LIFETIME: f32 = 30.0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize)]
 pub enum ItemKind {
     Medkit,
 
@@ -19,16 +31,28 @@ pub enum ItemKind {
     Plasma,
     Ak47Ammo,
     M4Ammo,
+    Rockets,
 
     // Weapons
     PlasmaGun,
     Ak47,
     M4,
     RocketLauncher,
+
+    // Keycards
+    RedKeycard,
+    GreenKeycard,
+    BlueKeycard,
+
+    Grenade,
+
+    // Armor
+    ArmorShard,
+    ArmorVest,
 }
 
 impl ItemKind {
-    fn from_id(id: u32) -> Result<ItemKind, String> {
+    pub fn from_id(id: u32) -> Result<ItemKind, String> {
         match id {
             0 => Ok(ItemKind::Medkit),
             1 => Ok(ItemKind::Plasma),
@@ -38,11 +62,18 @@ impl ItemKind {
             5 => Ok(ItemKind::Ak47),
             6 => Ok(ItemKind::M4),
             7 => Ok(ItemKind::RocketLauncher),
+            8 => Ok(ItemKind::RedKeycard),
+            9 => Ok(ItemKind::GreenKeycard),
+            10 => Ok(ItemKind::BlueKeycard),
+            11 => Ok(ItemKind::Grenade),
+            12 => Ok(ItemKind::ArmorShard),
+            13 => Ok(ItemKind::ArmorVest),
+            14 => Ok(ItemKind::Rockets),
             _ => Err(format!("Unknown item kind {}", id)),
         }
     }
 
-    fn id(self) -> u32 {
+    pub fn id(self) -> u32 {
         match self {
             ItemKind::Medkit => 0,
             ItemKind::Plasma => 1,
@@ -52,15 +83,55 @@ impl ItemKind {
             ItemKind::Ak47 => 5,
             ItemKind::M4 => 6,
             ItemKind::RocketLauncher => 7,
+            ItemKind::RedKeycard => 8,
+            ItemKind::GreenKeycard => 9,
+            ItemKind::BlueKeycard => 10,
+            ItemKind::Grenade => 11,
+            ItemKind::ArmorShard => 12,
+            ItemKind::ArmorVest => 13,
+            ItemKind::Rockets => 14,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ItemKind::Medkit => "Medkit",
+            ItemKind::Plasma => "Plasma",
+            ItemKind::Ak47Ammo => "AK-47 Ammo",
+            ItemKind::M4Ammo => "M4 Ammo",
+            ItemKind::Rockets => "Rockets",
+            ItemKind::PlasmaGun => "Plasma Rifle",
+            ItemKind::Ak47 => "AK-47",
+            ItemKind::M4 => "M4",
+            ItemKind::RocketLauncher => "Rocket Launcher",
+            ItemKind::RedKeycard => "Red Keycard",
+            ItemKind::GreenKeycard => "Green Keycard",
+            ItemKind::BlueKeycard => "Blue Keycard",
+            ItemKind::Grenade => "Grenade",
+            ItemKind::ArmorShard => "Armor Shard",
+            ItemKind::ArmorVest => "Armor Vest",
         }
     }
+
+    pub fn is_keycard(self) -> bool {
+        matches!(
+            self,
+            ItemKind::RedKeycard | ItemKind::GreenKeycard | ItemKind::BlueKeycard
+        )
+    }
 }
 
 pub struct Item {
     kind: ItemKind,
     pivot: Handle<Node>,
     model: Handle<Node>,
-    definition: &'static ItemDefinition,
+    definition: Arc<ItemDefinition>,
+    /// Overrides default amount of ammo, health, etc. the item gives.
+    amount: Option<u32>,
+    /// Time left until temporary item disappears, constant items have no lifetime.
+    lifetime: Option<f32>,
+    /// Time left until picked up constant item becomes active again.
+    respawn_timer: f32,
     pub sender: Option<Sender<Message>>,
 }
 
@@ -70,78 +141,60 @@ impl Default for Item {
             kind: ItemKind::Medkit,
             pivot: Default::default(),
             model: Default::default(),
-            definition: Self::get_definition(ItemKind::Medkit),
+            definition: Default::default(),
+            amount: None,
+            lifetime: None,
+            respawn_timer: 0.0,
             sender: None,
         }
     }
 }
 
+#[derive(Deserialize, Debug)]
 pub struct ItemDefinition {
-    model: &'static str,
+    kind: ItemKind,
+    model: String,
     scale: f32,
+    /// Time in seconds for which constant item is inactive after it was picked up.
+    respawn_time: f32,
 }
 
-impl Item {
-    pub fn get_definition(kind: ItemKind) -> &'static ItemDefinition {
-        match kind {
-            ItemKind::Medkit => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/medkit.fbx",
-                    scale: 1.0,
-                };
-                &DEFINITION
-            }
-            ItemKind::Plasma => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/yellow_box.FBX",
-                    scale: 0.25,
-                };
-                &DEFINITION
-            }
-            ItemKind::Ak47Ammo => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/box_medium.FBX",
-                    scale: 0.30,
-                };
-                &DEFINITION
-            }
-            ItemKind::M4Ammo => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/box_small.FBX",
-                    scale: 0.30,
-                };
-                &DEFINITION
-            }
-            ItemKind::PlasmaGun => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/plasma_rifle.FBX",
-                    scale: 3.0,
-                };
-                &DEFINITION
-            }
-            ItemKind::Ak47 => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/ak47.FBX",
-                    scale: 3.0,
-                };
-                &DEFINITION
-            }
-            ItemKind::M4 => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/m4.FBX",
-                    scale: 3.0,
-                };
-                &DEFINITION
-            }
-            ItemKind::RocketLauncher => {
-                static DEFINITION: ItemDefinition = ItemDefinition {
-                    model: "data/models/Rpg7.FBX",
-                    scale: 3.0,
-                };
-                &DEFINITION
-            }
+impl Default for ItemDefinition {
+    fn default() -> Self {
+        Self {
+            kind: ItemKind::Medkit,
+            model: Default::default(),
+            scale: 1.0,
+            respawn_time: 0.0,
         }
     }
+}
+
+static DEFINITIONS: Storage<ItemDefinition> = RwLock::new(None);
+
+impl Definition for ItemDefinition {
+    const PATH: &'static str = "data/items";
+
+    fn id(&self) -> u32 {
+        self.kind.id()
+    }
+
+    fn name(&self) -> &str {
+        self.kind.description()
+    }
+
+    fn storage() -> &'static Storage<Self> {
+        &DEFINITIONS
+    }
+}
+
+impl Item {
+    pub fn get_definition(kind: ItemKind) -> Arc<ItemDefinition> {
+        definition::registry::<ItemDefinition>()
+            .get(kind.id())
+            .cloned()
+            .unwrap_or_else(|| panic!("item definition {} is not loaded", kind.description()))
+    }
 
     pub async fn new(
         kind: ItemKind,
@@ -152,11 +205,25 @@ impl Item {
     ) -> Self {
         let definition = Self::get_definition(kind);
 
-        let model = resource_manager
-            .request_model(Path::new(definition.model))
+        // Item without model still can be picked up, so missing model is not fatal.
+        let model = match resource_manager
+            .request_model(Path::new(&definition.model))
             .await
-            .unwrap()
-            .instantiate_geometry(scene);
+        {
+            Ok(model) => model.instantiate_geometry(scene),
+            Err(e) => {
+                Log::writeln(
+                    MessageKind::Error,
+                    format!(
+                        "Unable to load model {} of item {}. Reason: {:?}",
+                        definition.model,
+                        kind.description(),
+                        e
+                    ),
+                );
+                Handle::NONE
+            }
+        };
 
         let pivot = BaseBuilder::new()
             .with_local_transform(
@@ -171,12 +238,15 @@ impl Item {
             )
             .build(&mut scene.graph);
 
-        scene.graph.link_nodes(model, pivot);
+        if model.is_some() {
+            scene.graph.link_nodes(model, pivot);
+        }
 
         Self {
             pivot,
             kind,
             model,
+            definition,
             sender: Some(sender),
             ..Default::default()
         }
@@ -194,6 +264,61 @@ impl Item {
         self.kind
     }
 
+    pub fn amount(&self) -> Option<u32> {
+        self.amount
+    }
+
+    pub fn set_amount(&mut self, amount: Option<u32>) {
+        self.amount = amount;
+    }
+
+    /// Makes item disappear after `TEMPORARY_ITEM_LIFETIME` seconds or when picked up.
+    pub fn make_temporary(&mut self) {
+        self.lifetime = Some(TEMPORARY_ITEM_LIFETIME);
+    }
+
+    pub fn is_temporary(&self) -> bool {
+        self.lifetime.is_some()
+    }
+
+    /// Returns true if item can be picked up.
+    pub fn is_active(&self) -> bool {
+        self.respawn_timer <= 0.0
+    }
+
+    /// Hides constant item until its respawn time passes, temporary items must be removed
+    /// by the caller instead.
+    pub fn deactivate(&mut self, graph: &mut Graph) {
+        self.respawn_timer = self.definition.respawn_time;
+        graph[self.pivot].set_visibility(false);
+    }
+
+    fn update(&mut self, graph: &mut Graph, dt: f32) {
+        if let Some(lifetime) = self.lifetime.as_mut() {
+            *lifetime -= dt;
+        }
+
+        if self.respawn_timer > 0.0 {
+            self.respawn_timer -= dt;
+            if self.respawn_timer <= 0.0 {
+                graph[self.pivot].set_visibility(true);
+            }
+        }
+    }
+
+    /// Picks up reloaded definition, new scale is applied at once, but model stays the same.
+    pub fn refresh_definition(&mut self, graph: &mut Graph) {
+        self.definition = Self::get_definition(self.kind);
+        let scale = self.definition.scale;
+        graph[self.pivot]
+            .local_transform_mut()
+            .set_scale(Vector3::new(scale, scale, scale));
+    }
+
+    fn is_expired(&self) -> bool {
+        self.lifetime.map_or(false, |lifetime| lifetime <= 0.0)
+    }
+
     fn cleanup(&self, graph: &mut Graph) {
         graph.remove_node(self.pivot)
     }
@@ -212,6 +337,9 @@ impl Visit for Item {
         self.definition = Self::get_definition(self.kind);
         self.model.visit("Model", visitor)?;
         self.pivot.visit("Pivot", visitor)?;
+        visit_since(&mut self.amount, "Amount", 3, visitor)?;
+        visit_since(&mut self.lifetime, "Lifetime", 9, visitor)?;
+        visit_since(&mut self.respawn_timer, "RespawnTimer", 9, visitor)?;
 
         visitor.leave_region()
     }
@@ -246,6 +374,10 @@ impl ItemContainer {
         self.pool.spawn(item)
     }
 
+    pub fn get(&self, item: Handle<Item>) -> &Item {
+        self.pool.borrow(item)
+    }
+
     pub fn get_mut(&mut self, item: Handle<Item>) -> &mut Item {
         self.pool.borrow_mut(item)
     }
@@ -254,6 +386,28 @@ impl ItemContainer {
         self.pool.is_valid_handle(item)
     }
 
+    pub fn remove(&mut self, item: Handle<Item>, graph: &mut Graph) {
+        self.pool.borrow(item).cleanup(graph);
+        self.pool.free(item);
+    }
+
+    /// Updates timers of items and removes expired temporary items.
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        for item in self.pool.iter_mut() {
+            item.update(&mut scene.graph, dt);
+        }
+
+        let expired = self
+            .pool
+            .pair_iter()
+            .filter(|(_, item)| item.is_expired())
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+        for item in expired {
+            self.remove(item, &mut scene.graph);
+        }
+    }
+
     pub fn pair_iter(&self) -> PoolPairIterator<Item> {
         self.pool.pair_iter()
     }
@@ -265,4 +419,8 @@ impl ItemContainer {
     pub fn iter_mut(&mut self) -> PoolIteratorMut<Item> {
         self.pool.iter_mut()
     }
+
+    pub fn count(&self) -> usize {
+        self.pool.alive_count()
+    }
 }
@@ -0,0 +1,213 @@
+//! Encounter director decides when bots are spawned. Spawn points with respawn time bring
+//! their bot back after it died, groups of spawn points started by triggers are spawned as
+//! waves. Every spawn waits in a queue until number of alive bots drops below a cap, and
+//! spawns are slowed down when the player is low on health or ammo.
+
+use crate::{
+    actor::{Actor, ActorContainer},
+    level::SpawnPoint,
+    message::Message,
+    weapon::WeaponContainer,
+};
+use rg3d::{
+    core::{
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    scene::Scene,
+};
+use serde::Deserialize;
+use std::sync::mpsc::Sender;
+
+/// Amount of ammo at which the player is considered to be fully armed.
+const COMFORTABLE_AMMO: u32 = 100;
+
+/// Per-level settings of the director, they're defined in level manifest.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DirectorSettings {
+    /// Director won't spawn new bots while this amount of bots is alive.
+    pub max_alive_bots: u32,
+    /// Interval in seconds between spawns when the player is in good shape.
+    pub min_spawn_interval: f32,
+    /// Interval in seconds between spawns when the player is almost dead or out of ammo.
+    pub max_spawn_interval: f32,
+    /// Spawn points closer to the player are skipped, so bots won't appear right in front
+    /// of the player.
+    pub min_spawn_distance: f32,
+}
+
+impl Default for DirectorSettings {
+    fn default() -> Self {
+        Self {
+            max_alive_bots: 8,
+            min_spawn_interval: 2.0,
+            max_spawn_interval: 10.0,
+            min_spawn_distance: 5.0,
+        }
+    }
+}
+
+impl Visit for DirectorSettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.max_alive_bots.visit("MaxAliveBots", visitor)?;
+        self.min_spawn_interval.visit("MinSpawnInterval", visitor)?;
+        self.max_spawn_interval.visit("MaxSpawnInterval", visitor)?;
+        self.min_spawn_distance.visit("MinSpawnDistance", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+#[derive(Default)]
+pub struct EncounterDirector {
+    pub settings: DirectorSettings,
+    /// Indices of spawn points waiting to be spawned.
+    queue: Vec<u32>,
+    spawn_timer: f32,
+}
+
+impl Visit for EncounterDirector {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.settings.visit("Settings", visitor)?;
+        self.queue.visit("Queue", visitor)?;
+        self.spawn_timer.visit("SpawnTimer", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl EncounterDirector {
+    pub fn new(settings: DirectorSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    fn enqueue(&mut self, id: usize) {
+        let id = id as u32;
+        if !self.queue.contains(&id) {
+            self.queue.push(id);
+        }
+    }
+
+    /// Queues every not yet spawned spawn point of a group.
+    pub fn start_wave(&mut self, spawn_points: &[SpawnPoint], group: &str) {
+        for (id, spawn_point) in spawn_points.iter().enumerate() {
+            if !spawn_point.spawned && spawn_point.group.as_deref() == Some(group) {
+                self.enqueue(id);
+            }
+        }
+    }
+
+    /// Returns value in [0; 1] range which tells how well the player is doing.
+    fn player_strength(
+        actors: &ActorContainer,
+        player: Handle<Actor>,
+        weapons: &WeaponContainer,
+    ) -> f32 {
+        if !actors.contains(player) {
+            return 0.0;
+        }
+
+        let player = actors.get(player);
+        let health = (player.get_health() / 100.0).min(1.0).max(0.0);
+        let ammo = player
+            .weapons()
+            .iter()
+            .map(|w| weapons[*w].ammo())
+            .sum::<u32>()
+            + player.inventory.total_ammo();
+        let ammo = (ammo as f32 / COMFORTABLE_AMMO as f32).min(1.0);
+
+        health.min(ammo)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        spawn_points: &mut [SpawnPoint],
+        actors: &ActorContainer,
+        player: Handle<Actor>,
+        weapons: &WeaponContainer,
+        scene: &Scene,
+        dt: f32,
+        sender: &Sender<Message>,
+    ) {
+        for (id, spawn_point) in spawn_points.iter_mut().enumerate() {
+            let respawn_time = match spawn_point.respawn_time {
+                Some(time) => time,
+                None => continue,
+            };
+
+            if spawn_point.spawned {
+                if actors.contains(spawn_point.bot) {
+                    // Remove corpse when it has finished dying, otherwise respawning spawn
+                    // point will fill the level with corpses.
+                    let bot = actors.get(spawn_point.bot);
+                    if bot.is_dead() && bot.can_be_removed(scene) {
+                        sender
+                            .send(Message::RemoveActor {
+                                actor: spawn_point.bot,
+                            })
+                            .unwrap();
+                        spawn_point.bot = Handle::NONE;
+                    }
+                } else {
+                    spawn_point.spawned = false;
+                    spawn_point.respawn_timer = respawn_time;
+                }
+            } else if spawn_point.respawn_timer > 0.0 {
+                spawn_point.respawn_timer -= dt;
+                if spawn_point.respawn_timer <= 0.0 {
+                    self.enqueue(id);
+                }
+            }
+        }
+
+        self.spawn_timer -= dt;
+        if self.spawn_timer > 0.0 || self.queue.is_empty() {
+            return;
+        }
+
+        let alive_bots = actors
+            .iter()
+            .filter(|actor| matches!(actor, Actor::Bot(_)) && !actor.is_dead())
+            .count();
+        if alive_bots >= self.settings.max_alive_bots as usize {
+            return;
+        }
+
+        let player_position = if actors.contains(player) {
+            Some(actors.get(player).position(&scene.physics))
+        } else {
+            None
+        };
+        let min_distance = self.settings.min_spawn_distance;
+        if let Some(index) = self.queue.iter().position(|&id| {
+            spawn_points.get(id as usize).map_or(true, |pt| {
+                player_position.map_or(true, |p| pt.position.metric_distance(&p) >= min_distance)
+            })
+        }) {
+            let id = self.queue.remove(index) as usize;
+            if let Some(spawn_point) = spawn_points.get_mut(id) {
+                if !spawn_point.spawned {
+                    spawn_point.spawned = true;
+                    sender
+                        .send(Message::SpawnBot { spawn_point_id: id })
+                        .unwrap();
+                }
+            }
+
+            let strength = Self::player_strength(actors, player, weapons);
+            self.spawn_timer = self.settings.min_spawn_interval
+                + (self.settings.max_spawn_interval - self.settings.min_spawn_interval)
+                    * (1.0 - strength);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Doors are sliding meshes of a level which are moved by kinematic rigid bodies. Door opens
+//! when an actor uses it and closes by itself when nobody stands in the doorway. Locked door
+//! needs a keycard, it stays unlocked after it was opened once.
+
+use crate::{
+    actor::{Actor, ActorContainer},
+    character::Character,
+    item::ItemKind,
+};
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Vector3},
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    physics::{
+        dynamics::{BodyStatus, RigidBodyBuilder},
+        geometry::ColliderBuilder,
+    },
+    scene::{node::Node, RigidBodyHandle, Scene},
+};
+
+/// Time in seconds door stays open after the doorway became empty.
+const STAY_OPEN_TIME: f32 = 4.0;
+
+/// Distance at which bots open doors on their way.
+const BOT_OPEN_DISTANCE: f32 = 1.5;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DoorState {
+    Closed,
+    Opening,
+    Open,
+    Closing,
+}
+
+impl DoorState {
+    pub fn id(self) -> u32 {
+        match self {
+            DoorState::Closed => 0,
+            DoorState::Opening => 1,
+            DoorState::Open => 2,
+            DoorState::Closing => 3,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(DoorState::Closed),
+            1 => Ok(DoorState::Opening),
+            2 => Ok(DoorState::Open),
+            3 => Ok(DoorState::Closing),
+            _ => Err(format!("Invalid door state {}", id)),
+        }
+    }
+}
+
+pub struct Door {
+    node: Handle<Node>,
+    body: RigidBodyHandle,
+    /// Bounds of closed door.
+    bounds: AxisAlignedBoundingBox,
+    /// Local position of the node of closed door.
+    local_position: Vector3<f32>,
+    /// Keycard required to open the door.
+    key: Option<ItemKind>,
+    /// Speed of opening in units of door height per second.
+    speed: f32,
+    state: DoorState,
+    openness: f32,
+    open_timer: f32,
+}
+
+impl Default for Door {
+    fn default() -> Self {
+        Self {
+            node: Default::default(),
+            body: Default::default(),
+            bounds: Default::default(),
+            local_position: Default::default(),
+            key: None,
+            speed: 1.0,
+            state: DoorState::Closed,
+            openness: 0.0,
+            open_timer: 0.0,
+        }
+    }
+}
+
+impl Visit for Door {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.node.visit("Node", visitor)?;
+        self.body.visit("Body", visitor)?;
+        self.bounds.visit("Bounds", visitor)?;
+        self.local_position.visit("LocalPosition", visitor)?;
+
+        let mut key = self.key.map(|k| k.id());
+        key.visit("Key", visitor)?;
+        let mut state = self.state.id();
+        state.visit("State", visitor)?;
+        if visitor.is_reading() {
+            self.key = key.map(ItemKind::from_id).transpose()?;
+            self.state = DoorState::from_id(state)?;
+        }
+
+        self.speed.visit("Speed", visitor)?;
+        self.openness.visit("Openness", visitor)?;
+        self.open_timer.visit("OpenTimer", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl Door {
+    /// Makes door from a mesh of the level, the mesh must not have its own collider.
+    pub fn new(node: Handle<Node>, key: Option<ItemKind>, speed: f32, scene: &mut Scene) -> Self {
+        let bounds = scene.graph[node].as_mesh().world_bounding_box();
+        let center = bounds.center();
+        let half_extents = (bounds.max - bounds.min).scale(0.5);
+
+        let body = scene.physics.add_body(
+            RigidBodyBuilder::new(BodyStatus::Kinematic)
+                .translation(center.x, center.y, center.z)
+                .build(),
+        );
+        scene.physics.add_collider(
+            ColliderBuilder::cuboid(half_extents.x, half_extents.y, half_extents.z).build(),
+            body,
+        );
+
+        Self {
+            node,
+            body,
+            bounds,
+            local_position: scene.graph[node].local_transform().position(),
+            key,
+            speed,
+            ..Default::default()
+        }
+    }
+
+    pub fn bounds(&self) -> &AxisAlignedBoundingBox {
+        &self.bounds
+    }
+
+    pub fn key(&self) -> Option<ItemKind> {
+        self.key
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state == DoorState::Open
+    }
+
+    pub fn can_be_opened_by(&self, character: &Character) -> bool {
+        self.key
+            .map_or(true, |key| character.inventory.has_keycard(key))
+    }
+
+    pub fn open(&mut self) {
+        if let DoorState::Closed | DoorState::Closing = self.state {
+            self.state = DoorState::Opening;
+        }
+        self.key = None;
+        self.open_timer = STAY_OPEN_TIME;
+    }
+
+    pub fn close(&mut self) {
+        if let DoorState::Open | DoorState::Opening = self.state {
+            self.state = DoorState::Closing;
+        }
+    }
+
+    /// Opens or closes the door, checks for keycard must be done by the caller.
+    pub fn toggle(&mut self) {
+        match self.state {
+            DoorState::Closed | DoorState::Closing => self.open(),
+            DoorState::Open | DoorState::Opening => self.close(),
+        }
+    }
+
+    /// Returns true if a point is within given distance from the door.
+    pub fn is_near(&self, point: Vector3<f32>, distance: f32) -> bool {
+        is_near(&self.bounds, point, distance)
+    }
+
+    /// Returns true if the door blocks a path segment for bots, which can't open locked doors.
+    /// Path points are vertices of navmesh which lie on the floor, so the segment is lifted to
+    /// the middle of the door.
+    pub fn blocks_path(&self, begin: Vector3<f32>, end: Vector3<f32>) -> bool {
+        let lift = Vector3::new(0.0, (self.bounds.max.y - self.bounds.min.y) * 0.5, 0.0);
+        self.key.is_some()
+            && !self.is_open()
+            && segment_intersects(begin + lift, end + lift, &self.bounds)
+    }
+
+    pub fn update(&mut self, scene: &mut Scene, actors: &ActorContainer, dt: f32) {
+        // Bots can't use keycards, but they open any unlocked door on their way.
+        if self.key.is_none()
+            && (self.state == DoorState::Closed || self.state == DoorState::Closing)
+            && actors.iter().any(|actor| {
+                matches!(actor, Actor::Bot(_))
+                    && !actor.is_dead()
+                    && self.is_near(actor.position(&scene.physics), BOT_OPEN_DISTANCE)
+            })
+        {
+            self.open();
+        }
+
+        match self.state {
+            DoorState::Opening => {
+                self.openness += self.speed * dt;
+                if self.openness >= 1.0 {
+                    self.openness = 1.0;
+                    self.state = DoorState::Open;
+                    self.open_timer = STAY_OPEN_TIME;
+                }
+            }
+            DoorState::Open => {
+                // Never close door in front of somebody.
+                if actors
+                    .iter()
+                    .any(|actor| self.is_near(actor.position(&scene.physics), 1.0))
+                {
+                    self.open_timer = STAY_OPEN_TIME;
+                } else {
+                    self.open_timer -= dt;
+                    if self.open_timer <= 0.0 {
+                        self.state = DoorState::Closing;
+                    }
+                }
+            }
+            DoorState::Closing => {
+                self.openness -= self.speed * dt;
+                if self.openness <= 0.0 {
+                    self.openness = 0.0;
+                    self.state = DoorState::Closed;
+                }
+            }
+            DoorState::Closed => (),
+        }
+
+        let offset = Vector3::new(
+            0.0,
+            (self.bounds.max.y - self.bounds.min.y) * self.openness,
+            0.0,
+        );
+
+        scene.graph[self.node]
+            .local_transform_mut()
+            .set_position(self.local_position + offset);
+
+        let position = self.bounds.center() + offset;
+        if let Some(body) = scene.physics.bodies.get_mut(self.body.into()) {
+            body.set_next_kinematic_position(Isometry3::translation(
+                position.x, position.y, position.z,
+            ));
+        }
+    }
+}
+
+/// Returns true if a point is within given distance from bounds.
+pub fn is_near(bounds: &AxisAlignedBoundingBox, point: Vector3<f32>, distance: f32) -> bool {
+    (0..3).all(|i| point[i] >= bounds.min[i] - distance && point[i] <= bounds.max[i] + distance)
+}
+
+fn segment_intersects(
+    begin: Vector3<f32>,
+    end: Vector3<f32>,
+    bounds: &AxisAlignedBoundingBox,
+) -> bool {
+    let dir = end - begin;
+    let mut t_min = 0.0f32;
+    let mut t_max = 1.0f32;
+    for i in 0..3 {
+        if dir[i].abs() <= std::f32::EPSILON {
+            if begin[i] < bounds.min[i] || begin[i] > bounds.max[i] {
+                return false;
+            }
+        } else {
+            let mut t1 = (bounds.min[i] - begin[i]) / dir[i];
+            let mut t2 = (bounds.max[i] - begin[i]) / dir[i];
+            if t1 > t2 {
+                std::mem::swap(&mut t1, &mut t2);
+            }
+            t_min = t_min.max(t1);
+            t_max = t_max.min(t2);
+            if t_min > t_max {
+                return false;
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,136 @@
+//! Elevator is a platform mesh of a level that moves vertically between its initial position
+//! and a position above it, it is moved by a kinematic rigid body so it carries actors.
+
+use crate::level::door::is_near;
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Vector3},
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    physics::{
+        dynamics::{BodyStatus, RigidBodyBuilder},
+        geometry::ColliderBuilder,
+    },
+    scene::{node::Node, RigidBodyHandle, Scene},
+};
+
+pub struct Elevator {
+    node: Handle<Node>,
+    body: RigidBodyHandle,
+    /// Bounds of the platform at bottom position.
+    bounds: AxisAlignedBoundingBox,
+    local_position: Vector3<f32>,
+    height: f32,
+    /// Speed in units per second.
+    speed: f32,
+    /// Current position between bottom (0.0) and top (1.0).
+    position: f32,
+    target: f32,
+}
+
+impl Default for Elevator {
+    fn default() -> Self {
+        Self {
+            node: Default::default(),
+            body: Default::default(),
+            bounds: Default::default(),
+            local_position: Default::default(),
+            height: 3.0,
+            speed: 1.0,
+            position: 0.0,
+            target: 0.0,
+        }
+    }
+}
+
+impl Visit for Elevator {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.node.visit("Node", visitor)?;
+        self.body.visit("Body", visitor)?;
+        self.bounds.visit("Bounds", visitor)?;
+        self.local_position.visit("LocalPosition", visitor)?;
+        self.height.visit("Height", visitor)?;
+        self.speed.visit("Speed", visitor)?;
+        self.position.visit("Position", visitor)?;
+        self.target.visit("Target", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl Elevator {
+    /// Makes elevator from a mesh of the level, the mesh must not have its own collider.
+    pub fn new(node: Handle<Node>, height: f32, speed: f32, scene: &mut Scene) -> Self {
+        let bounds = scene.graph[node].as_mesh().world_bounding_box();
+        let center = bounds.center();
+        let half_extents = (bounds.max - bounds.min).scale(0.5);
+
+        let body = scene.physics.add_body(
+            RigidBodyBuilder::new(BodyStatus::Kinematic)
+                .translation(center.x, center.y, center.z)
+                .build(),
+        );
+        scene.physics.add_collider(
+            ColliderBuilder::cuboid(half_extents.x, half_extents.y, half_extents.z).build(),
+            body,
+        );
+
+        Self {
+            node,
+            body,
+            bounds,
+            local_position: scene.graph[node].local_transform().position(),
+            height,
+            speed,
+            ..Default::default()
+        }
+    }
+
+    fn offset(&self) -> Vector3<f32> {
+        Vector3::new(0.0, self.height * self.position, 0.0)
+    }
+
+    /// Returns true if a point is within given distance from current position of the platform.
+    pub fn is_near(&self, point: Vector3<f32>, distance: f32) -> bool {
+        is_near(&self.bounds, point - self.offset(), distance)
+    }
+
+    /// Sends elevator to opposite end, moving elevator turns back.
+    pub fn toggle(&mut self) {
+        self.target = if self.target > 0.5 { 0.0 } else { 1.0 };
+    }
+
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        let step = self.speed * dt / self.height.max(std::f32::EPSILON);
+        if self.position < self.target {
+            self.position = (self.position + step).min(self.target);
+        } else if self.position > self.target {
+            self.position = (self.position - step).max(self.target);
+        }
+
+        let offset = self.offset();
+
+        scene.graph[self.node]
+            .local_transform_mut()
+            .set_position(self.local_position + offset);
+
+        let position = self.bounds.center() + offset;
+        if let Some(body) = scene.physics.bodies.get_mut(self.body.into()) {
+            body.set_next_kinematic_position(Isometry3::translation(
+                position.x, position.y, position.z,
+            ));
+        }
+    }
+
+    pub fn bounds(&self) -> AxisAlignedBoundingBox {
+        let offset = self.offset();
+        let mut bounds = self.bounds;
+        bounds.min += offset;
+        bounds.max += offset;
+        bounds
+    }
+}
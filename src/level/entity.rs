@@ -0,0 +1,368 @@
+//! Entity registry turns marker nodes of a level scene into gameplay entities. A marker is a
+//! scene node whose name starts with a registered prefix, optionally followed by parameters in
+//! square brackets, for example `Zombie[weapon=Ak47,health=200]` or `Ammo_M4[amount=50]`.
+//!
+//! `Trigger` markers describe their condition, actor filter, mode and actions with parameters:
+//! `Trigger[on=enter,filter=player,mode=once,spawn=ambush,text=Watch out!]`. Supported actions
+//! are `spawn` (group of spawn points), `sound` (path), `damage` (amount per activation), `text`
+//! and `end=true` which ends the level.
+//!
+//! Bot markers are named after bot definitions from `data/bots`, they accept `health`,
+//! `weapon`, `group` (spawned by triggers as a wave) and `respawn` (seconds between death of
+//! a bot and its respawn).
+//!
+//! `Door[key=red,speed=2]` and `Elevator[height=4,speed=1.5]` markers turn meshes into doors
+//! and elevators, keys are `red`, `green` and `blue`.
+//!
+//! Registry only collects description of the level (`LevelLayout`), actual entities are created
+//! by `analyze` because that requires async resource loading.
+
+use crate::{
+    bot::{BotDefinition, BotKind},
+    definition,
+    item::ItemKind,
+    level::{
+        trigger::{ActorFilter, Trigger, TriggerAction, TriggerCondition, TriggerMode},
+        SpawnPoint,
+    },
+    weapon::WeaponKind,
+};
+use rg3d::{
+    core::{algebra::Vector3, pool::Handle},
+    scene::{node::Node, Scene},
+    utils::log::{Log, MessageKind},
+};
+use std::{collections::HashMap, str::FromStr};
+
+pub struct DoorSpawn {
+    pub node: Handle<Node>,
+    pub key: Option<ItemKind>,
+    pub speed: f32,
+}
+
+pub struct ElevatorSpawn {
+    pub node: Handle<Node>,
+    pub height: f32,
+    pub speed: f32,
+}
+
+pub struct ItemSpawn {
+    pub kind: ItemKind,
+    pub position: Vector3<f32>,
+    /// Overrides default amount of ammo, health, etc. the item gives.
+    pub amount: Option<u32>,
+}
+
+/// Description of gameplay entities of a level, collected from markers.
+#[derive(Default)]
+pub struct LevelLayout {
+    pub items: Vec<ItemSpawn>,
+    pub spawn_points: Vec<SpawnPoint>,
+    /// Triggers with volume markers, bounds of triggers are taken from the markers.
+    pub triggers: Vec<(Handle<Node>, Trigger)>,
+    pub level_exits: Vec<Handle<Node>>,
+    pub checkpoints: Vec<Handle<Node>>,
+    pub doors: Vec<DoorSpawn>,
+    pub elevators: Vec<ElevatorSpawn>,
+    pub player_spawn_position: Vector3<f32>,
+}
+
+pub struct Marker<'a> {
+    pub handle: Handle<Node>,
+    pub node: &'a Node,
+    /// Full name of the node, used for diagnostics.
+    pub name: &'a str,
+    params: HashMap<String, String>,
+}
+
+impl<'a> Marker<'a> {
+    /// Returns and consumes value of a parameter. Parameter that can't be parsed is reported
+    /// and treated as missing.
+    pub fn param<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        let value = self.params.remove(key)?;
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!(
+                        "Marker {}: invalid value {} of parameter {}!",
+                        self.name, value, key
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    pub fn position(&self) -> Vector3<f32> {
+        self.node.global_position()
+    }
+
+    /// Checks that marker is a mesh, volume markers use bounds of the mesh.
+    pub fn require_mesh(&self) -> bool {
+        if let Node::Mesh(_) = self.node {
+            true
+        } else {
+            Log::writeln(
+                MessageKind::Warning,
+                format!("Marker {} must be a mesh!", self.name),
+            );
+            false
+        }
+    }
+}
+
+/// Splits node name into prefix part and parameters: `Zombie1[health=200]` gives `Zombie1`
+/// and `health=200`.
+fn split_name(name: &str) -> (&str, Option<&str>) {
+    if let (Some(begin), Some(end)) = (name.find('['), name.rfind(']')) {
+        if begin < end {
+            return (&name[..begin], Some(&name[(begin + 1)..end]));
+        }
+    }
+    (name, None)
+}
+
+fn parse_params(name: &str, params: &str) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for pair in params
+        .split(',')
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+    {
+        let mut parts = pair.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => {
+                map.insert(key.trim().to_owned(), value.trim().to_owned());
+            }
+            _ => Log::writeln(
+                MessageKind::Warning,
+                format!("Marker {}: malformed parameter {}!", name, pair),
+            ),
+        }
+    }
+    map
+}
+
+pub type SpawnHandler = Box<dyn Fn(&mut Marker, &mut LevelLayout) + Send + Sync>;
+
+pub struct EntityRegistry {
+    handlers: Vec<(String, SpawnHandler)>,
+}
+
+impl Default for EntityRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register("Medkit", item(ItemKind::Medkit))
+            .register("Ammo_Ak47", item(ItemKind::Ak47Ammo))
+            .register("Ammo_M4", item(ItemKind::M4Ammo))
+            .register("Ammo_Plasma", item(ItemKind::Plasma))
+            .register("Ammo_Rockets", item(ItemKind::Rockets))
+            .register("Keycard_Red", item(ItemKind::RedKeycard))
+            .register("Keycard_Green", item(ItemKind::GreenKeycard))
+            .register("Keycard_Blue", item(ItemKind::BlueKeycard))
+            .register("Grenade", item(ItemKind::Grenade))
+            .register("Armor_Shard", item(ItemKind::ArmorShard))
+            .register("Armor_Vest", item(ItemKind::ArmorVest))
+            .register(
+                "PlayerSpawnPoint",
+                Box::new(|marker: &mut Marker, layout: &mut LevelLayout| {
+                    layout.player_spawn_position = marker.position()
+                }),
+            )
+            .register(
+                "DeathZone",
+                volume(|layout, handle| layout.triggers.push((handle, Trigger::death_zone()))),
+            )
+            .register("Trigger", Box::new(trigger))
+            .register("Door", Box::new(door))
+            .register("Elevator", Box::new(elevator))
+            .register(
+                "LevelExit",
+                volume(|layout, handle| layout.level_exits.push(handle)),
+            )
+            .register(
+                "Checkpoint",
+                volume(|layout, handle| layout.checkpoints.push(handle)),
+            );
+        for definition in definition::registry::<BotDefinition>().iter() {
+            registry.register(&definition.name, bot(definition.kind()));
+        }
+        registry
+    }
+}
+
+impl EntityRegistry {
+    pub fn empty() -> Self {
+        Self {
+            handlers: Default::default(),
+        }
+    }
+
+    /// Registers spawn handler for markers with given prefix. When multiple prefixes match
+    /// a name, the longest one wins.
+    pub fn register(&mut self, prefix: &str, handler: SpawnHandler) -> &mut Self {
+        self.handlers.push((prefix.to_owned(), handler));
+        self
+    }
+
+    fn find(&self, name: &str) -> Option<&SpawnHandler> {
+        self.handlers
+            .iter()
+            .filter(|(prefix, _)| name.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, handler)| handler)
+    }
+
+    /// Runs handlers for every marker of the scene.
+    pub fn process(&self, scene: &Scene) -> LevelLayout {
+        let mut layout = LevelLayout::default();
+
+        for (handle, node) in scene.graph.pair_iter() {
+            let name = node.name();
+            let (base, params) = split_name(name);
+
+            match self.find(base) {
+                Some(handler) => {
+                    let mut marker = Marker {
+                        handle,
+                        node,
+                        name,
+                        params: params.map_or_else(HashMap::new, |p| parse_params(name, p)),
+                    };
+
+                    handler(&mut marker, &mut layout);
+
+                    for key in marker.params.keys() {
+                        Log::writeln(
+                            MessageKind::Warning,
+                            format!("Marker {}: unknown parameter {}!", name, key),
+                        );
+                    }
+                }
+                None => {
+                    // Ordinary scene nodes are not markers, but a node with parameters is
+                    // definitely meant to be one.
+                    if params.is_some() {
+                        Log::writeln(MessageKind::Warning, format!("Unknown marker {}!", name));
+                    }
+                }
+            }
+        }
+
+        layout
+    }
+}
+
+fn item(kind: ItemKind) -> SpawnHandler {
+    Box::new(move |marker: &mut Marker, layout: &mut LevelLayout| {
+        layout.items.push(ItemSpawn {
+            kind,
+            position: marker.position(),
+            amount: marker.param("amount"),
+        })
+    })
+}
+
+fn bot(kind: BotKind) -> SpawnHandler {
+    Box::new(move |marker: &mut Marker, layout: &mut LevelLayout| {
+        layout.spawn_points.push(SpawnPoint {
+            position: marker.position(),
+            bot_kind: kind,
+            spawned: false,
+            health: marker.param("health"),
+            weapon: marker.param::<WeaponKind>("weapon"),
+            group: marker.param("group"),
+            respawn_time: marker.param("respawn"),
+            ..Default::default()
+        })
+    })
+}
+
+fn volume(add: fn(&mut LevelLayout, Handle<Node>)) -> SpawnHandler {
+    Box::new(move |marker: &mut Marker, layout: &mut LevelLayout| {
+        if marker.require_mesh() {
+            add(layout, marker.handle)
+        }
+    })
+}
+
+fn trigger(marker: &mut Marker, layout: &mut LevelLayout) {
+    if !marker.require_mesh() {
+        return;
+    }
+
+    let mut actions = Vec::new();
+    if let Some(group) = marker.param("spawn") {
+        actions.push(TriggerAction::SpawnBots { group });
+    }
+    if let Some(path) = marker.param("sound") {
+        actions.push(TriggerAction::PlaySound { path });
+    }
+    if let Some(amount) = marker.param("damage") {
+        actions.push(TriggerAction::Damage { amount });
+    }
+    if let Some(text) = marker.param("text") {
+        actions.push(TriggerAction::ShowText { text });
+    }
+    if marker.param("end").unwrap_or(false) {
+        actions.push(TriggerAction::EndLevel);
+    }
+    if actions.is_empty() {
+        Log::writeln(
+            MessageKind::Warning,
+            format!("Trigger {} has no actions!", marker.name),
+        );
+    }
+
+    layout.triggers.push((
+        marker.handle,
+        Trigger {
+            condition: marker.param("on").unwrap_or(TriggerCondition::Enter),
+            filter: marker.param("filter").unwrap_or(ActorFilter::Player),
+            mode: marker.param("mode").unwrap_or(TriggerMode::Once),
+            interval: marker.param("interval").unwrap_or(0.0),
+            actions,
+            ..Default::default()
+        },
+    ));
+}
+
+fn door(marker: &mut Marker, layout: &mut LevelLayout) {
+    if !marker.require_mesh() {
+        return;
+    }
+
+    let key = marker
+        .param::<String>("key")
+        .and_then(|color| match color.as_str() {
+            "red" => Some(ItemKind::RedKeycard),
+            "green" => Some(ItemKind::GreenKeycard),
+            "blue" => Some(ItemKind::BlueKeycard),
+            _ => {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!("Marker {}: unknown key {}!", marker.name, color),
+                );
+                None
+            }
+        });
+
+    layout.doors.push(DoorSpawn {
+        node: marker.handle,
+        key,
+        speed: marker.param("speed").unwrap_or(1.0),
+    });
+}
+
+fn elevator(marker: &mut Marker, layout: &mut LevelLayout) {
+    if marker.require_mesh() {
+        layout.elevators.push(ElevatorSpawn {
+            node: marker.handle,
+            height: marker.param("height").unwrap_or(3.0),
+            speed: marker.param("speed").unwrap_or(1.0),
+        });
+    }
+}
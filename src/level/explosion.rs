@@ -0,0 +1,130 @@
+//! Explosions damage actors around their center, damage decreases with distance and is
+//! blocked by level geometry. Explosions push nearby rigid bodies and shake camera of the
+//! player.
+
+use crate::{
+    actor::{Actor, ActorContainer},
+    character::DamageType,
+    message::Message,
+};
+use rg3d::{
+    core::{algebra::Vector3, math::ray::Ray, pool::Handle},
+    physics::geometry::InteractionGroups,
+    scene::{
+        physics::{Physics, RayCastOptions},
+        Scene,
+    },
+};
+use std::sync::mpsc::Sender;
+
+/// Impulse that is applied to a rigid body at the center of explosion per unit of damage.
+const IMPULSE_PER_DAMAGE: f32 = 0.05;
+
+/// Camera of the player shakes when the player is within this amount of explosion radii.
+const SHAKE_RADII: f32 = 3.0;
+
+/// Distance along surface normal from which visibility of actors is checked, so the surface
+/// that was hit does not cover actors from the explosion.
+const VISIBILITY_OFFSET: f32 = 0.05;
+
+pub struct Explosion {
+    pub position: Vector3<f32>,
+    /// Normal of surface at the center of explosion.
+    pub normal: Vector3<f32>,
+    pub radius: f32,
+    /// Damage at the center of explosion, it decreases linearly down to zero at the radius.
+    pub damage: f32,
+    /// Actor who caused the explosion, damage and kills are attributed to the actor.
+    pub who: Handle<Actor>,
+}
+
+impl Explosion {
+    /// Returns part of damage at given distance from center, in [0; 1] range.
+    fn falloff(&self, distance: f32) -> f32 {
+        (1.0 - distance / self.radius).max(0.0)
+    }
+
+    /// Checks whether there is no level geometry between the center of explosion and an actor.
+    fn is_visible(&self, physics: &mut Physics, actors: &ActorContainer, actor: &Actor) -> bool {
+        let origin = self.position + self.normal.scale(VISIBILITY_OFFSET);
+        let target = actor.position(physics);
+        let ray = match Ray::from_two_points(&origin, &target) {
+            Some(ray) => ray,
+            None => return true,
+        };
+
+        let mut query_buffer = Vec::new();
+        physics.cast_ray(
+            RayCastOptions {
+                ray,
+                max_len: ray.dir.norm(),
+                groups: InteractionGroups::all(),
+                sort_results: true,
+            },
+            &mut query_buffer,
+        );
+
+        for hit in query_buffer.iter() {
+            let body = physics.colliders.get(hit.collider.into()).unwrap().parent();
+            if actor.get_body() == body.into() {
+                return true;
+            }
+            // Other actors and moving props do not cover from explosion.
+            if actors.iter().any(|a| a.get_body() == body.into()) {
+                continue;
+            }
+            if physics.bodies.get(body).map_or(false, |b| b.is_static()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn apply(&self, scene: &mut Scene, actors: &mut ActorContainer, sender: &Sender<Message>) {
+        for (actor_handle, actor) in actors.pair_iter() {
+            let distance = actor
+                .position(&scene.physics)
+                .metric_distance(&self.position);
+            if distance < self.radius && self.is_visible(&mut scene.physics, actors, actor) {
+                sender
+                    .send(Message::DamageActor {
+                        actor: actor_handle,
+                        who: self.who,
+                        amount: self.damage * self.falloff(distance),
+                        damage_type: DamageType::Explosion,
+                        position: None,
+                        location: None,
+                    })
+                    .unwrap();
+            }
+        }
+
+        for (_, body) in scene.physics.bodies.iter_mut() {
+            if !body.is_dynamic() {
+                continue;
+            }
+            let offset = body.position().translation.vector - self.position;
+            let distance = offset.norm();
+            if distance < self.radius {
+                let direction = offset
+                    .try_normalize(std::f32::EPSILON)
+                    .unwrap_or_else(Vector3::y);
+                let impulse = self.damage * IMPULSE_PER_DAMAGE * self.falloff(distance);
+                body.apply_impulse(direction.scale(impulse), true);
+            }
+        }
+
+        for actor in actors.iter_mut() {
+            if let Actor::Player(player) = actor {
+                let distance = player
+                    .position(&scene.physics)
+                    .metric_distance(&self.position);
+                let strength = 1.0 - distance / (self.radius * SHAKE_RADII);
+                if strength > 0.0 {
+                    player.shake_camera(strength);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Level manifest describes every map of the game and the order in which they're played.
+//! It is loaded once at startup from `data/levels/manifest.ron`, so new maps can be added
+//! without touching the code.
+
+use crate::level::{director::DirectorSettings, objective::ObjectiveDefinition};
+use serde::Deserialize;
+use std::{fs::File, path::Path};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LevelDefinition {
+    /// Unique identifier of a level, used in `Message::LoadLevel` and in saves.
+    pub id: String,
+    /// Path to scene file of the level.
+    pub scene: String,
+    /// Human-readable name of the level.
+    pub name: String,
+    /// Identifier of a level that will be loaded when player reaches level exit.
+    pub next: Option<String>,
+    /// Settings of encounter director of the level.
+    #[serde(default)]
+    pub director: DirectorSettings,
+    /// Objectives of the level in order of completion.
+    #[serde(default)]
+    pub objectives: Vec<ObjectiveDefinition>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+pub struct LevelManifest {
+    /// Identifier of a level which is loaded when new game is started.
+    pub start: String,
+    pub levels: Vec<LevelDefinition>,
+}
+
+impl LevelManifest {
+    pub const PATH: &'static str = "data/levels/manifest.ron";
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let file = File::open(path.as_ref()).map_err(|e| {
+            format!(
+                "Unable to open level manifest {}. Reason: {}",
+                path.as_ref().display(),
+                e
+            )
+        })?;
+        let manifest: LevelManifest = ron::de::from_reader(file).map_err(|e| {
+            format!(
+                "Unable to parse level manifest {}. Reason: {}",
+                path.as_ref().display(),
+                e
+            )
+        })?;
+
+        if manifest.definition(&manifest.start).is_none() {
+            return Err(format!(
+                "Start level {} is not defined in level manifest!",
+                manifest.start
+            ));
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn definition(&self, id: &str) -> Option<&LevelDefinition> {
+        self.levels.iter().find(|l| l.id == id)
+    }
+}
@@ -0,0 +1,1907 @@
+use crate::{
+    actor::{Actor, ActorContainer},
+    bot::{Bot, BotKind},
+    character::{ArmorTier, DamageType, ARMOR_SHARD_AMOUNT, ARMOR_VEST_AMOUNT},
+    control_scheme::ControlScheme,
+    effects::{self, EffectKind},
+    hitbox::HitLocation,
+    inventory::{AmmoKind, MEDKIT_HEALTH},
+    item::{Item, ItemContainer, ItemKind},
+    level::{
+        director::EncounterDirector,
+        door::Door,
+        elevator::Elevator,
+        entity::{EntityRegistry, LevelLayout},
+        explosion::Explosion,
+        manifest::LevelDefinition,
+        objective::ObjectiveContainer,
+        stats::{LevelStats, Outcome},
+        trigger::Trigger,
+    },
+    message::Message,
+    player::Player,
+    replay::{Replay, ReplayPlayer},
+    rng::GameRng,
+    save::{read_version, visit_since, SaveMetadata},
+    weapon::{
+        projectile::{Projectile, ProjectileContainer, ProjectileKind},
+        ray_hit, Weapon, WeaponContainer, WeaponKind,
+    },
+    GameEngine, GameTime, FIXED_FPS,
+};
+use rg3d::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        color::Color,
+        math::{aabb::AxisAlignedBoundingBox, ray::Ray, PositionProvider},
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    engine::resource_manager::ResourceManager,
+    event::Event,
+    physics::{
+        crossbeam,
+        geometry::{ContactEvent, InteractionGroups, ProximityEvent},
+        pipeline::ChannelEventCollector,
+    },
+    rand::Rng,
+    renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+    scene::{
+        self,
+        base::BaseBuilder,
+        mesh::{MeshBuilder, RenderPath},
+        node::Node,
+        physics::RayCastOptions,
+        transform::TransformBuilder,
+        Scene,
+    },
+    sound::{
+        context::{self, Context},
+        effects::{BaseEffect, Effect, EffectInput},
+        source::{generic::GenericSourceBuilder, spatial::SpatialSourceBuilder, Status},
+    },
+    utils::{
+        log::{Log, MessageKind},
+        navmesh::Navmesh,
+    },
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Arc, RwLock},
+    time::Duration,
+};
+
+pub mod director;
+pub mod door;
+pub mod elevator;
+pub mod entity;
+pub mod explosion;
+pub mod manifest;
+pub mod objective;
+pub mod stats;
+pub mod trigger;
+
+pub const RESPAWN_TIME: f32 = 4.0;
+
+/// Max distance between an actor and a door or an elevator it can use.
+pub const USE_DISTANCE: f32 = 1.5;
+
+#[derive(Default)]
+pub struct SoundManager {
+    context: Context,
+    reverb: Handle<Effect>,
+}
+
+impl SoundManager {
+    pub fn new(context: Context) -> Self {
+        let mut base_effect = BaseEffect::default();
+        base_effect.set_gain(0.7);
+        let mut reverb = rg3d::sound::effects::reverb::Reverb::new(base_effect);
+        reverb.set_dry(0.5);
+        reverb.set_wet(0.5);
+        reverb.set_decay_time(Duration::from_secs_f32(3.0));
+        let reverb = context
+            .state()
+            .add_effect(rg3d::sound::effects::Effect::Reverb(reverb));
+
+        let hrtf_sphere = rg3d::sound::hrtf::HrirSphere::from_file(
+            "data/sounds/IRC_1040_C.bin",
+            context::SAMPLE_RATE,
+        )
+        .unwrap();
+        context
+            .state()
+            .set_renderer(rg3d::sound::renderer::Renderer::HrtfRenderer(
+                rg3d::sound::renderer::hrtf::HrtfRenderer::new(hrtf_sphere),
+            ));
+
+        Self { context, reverb }
+    }
+
+    pub async fn handle_message(&mut self, resource_manager: ResourceManager, message: &Message) {
+        let mut state = self.context.state();
+
+        match message {
+            Message::PlaySound {
+                path,
+                position,
+                gain,
+                rolloff_factor,
+                radius,
+            } => {
+                let shot_buffer = resource_manager
+                    .request_sound_buffer(path, false)
+                    .await
+                    .unwrap();
+                let shot_sound = SpatialSourceBuilder::new(
+                    GenericSourceBuilder::new(shot_buffer.into())
+                        .with_status(Status::Playing)
+                        .with_play_once(true)
+                        .with_gain(*gain)
+                        .build()
+                        .unwrap(),
+                )
+                .with_position(*position)
+                .with_radius(*radius)
+                .with_rolloff_factor(*rolloff_factor)
+                .build_source();
+                let source = state.add_source(shot_sound);
+                state
+                    .effect_mut(self.reverb)
+                    .add_input(EffectInput::direct(source));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Visit for SoundManager {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.context.visit("Context", visitor)?;
+        self.reverb.visit("Reverb", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+pub struct Level {
+    /// Identifier of level definition from level manifest.
+    pub id: String,
+    next_level: Option<String>,
+    map_root: Handle<Node>,
+    pub scene: Handle<Scene>,
+    player: Handle<Actor>,
+    projectiles: ProjectileContainer,
+    pub actors: ActorContainer,
+    weapons: WeaponContainer,
+    items: ItemContainer,
+    spawn_points: Vec<SpawnPoint>,
+    sender: Option<Sender<Message>>,
+    pub navmesh: Handle<Navmesh>,
+    pub control_scheme: Option<Arc<RwLock<ControlScheme>>>,
+    triggers: Vec<Trigger>,
+    level_exits: Vec<LevelExit>,
+    checkpoints: Vec<Checkpoint>,
+    doors: Vec<Door>,
+    elevators: Vec<Elevator>,
+    director: EncounterDirector,
+    objectives: ObjectiveContainer,
+    stats: LevelStats,
+    /// Position at which player will be respawned after death, it is set when player reaches
+    /// a checkpoint. Death without reached checkpoint ends the match.
+    respawn_position: Option<Vector3<f32>>,
+    player_respawn_timer: f32,
+    /// Set when end of level was requested, so match is ended only once.
+    ended: bool,
+    time: f32,
+    /// Amount of fixed steps performed so far, input of recordings is bound to it.
+    tick: u64,
+    sound_manager: SoundManager,
+    proximity_events_receiver: Option<crossbeam::channel::Receiver<ProximityEvent>>,
+    contact_events_receiver: Option<crossbeam::channel::Receiver<ContactEvent>>,
+    beam: Option<Arc<RwLock<SurfaceSharedData>>>,
+    rng: GameRng,
+    recording: Option<Replay>,
+    playback: Option<ReplayPlayer>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            id: Default::default(),
+            next_level: None,
+            map_root: Default::default(),
+            projectiles: ProjectileContainer::new(),
+            actors: ActorContainer::new(),
+            scene: Default::default(),
+            player: Handle::NONE,
+            weapons: WeaponContainer::new(),
+            items: ItemContainer::new(),
+            spawn_points: Default::default(),
+            sender: None,
+            navmesh: Default::default(),
+            control_scheme: None,
+            triggers: Default::default(),
+            level_exits: Default::default(),
+            checkpoints: Default::default(),
+            doors: Default::default(),
+            elevators: Default::default(),
+            director: Default::default(),
+            objectives: Default::default(),
+            stats: Default::default(),
+            respawn_position: None,
+            player_respawn_timer: 0.0,
+            ended: false,
+            time: 0.0,
+            tick: 0,
+            sound_manager: Default::default(),
+            proximity_events_receiver: None,
+            contact_events_receiver: None,
+            beam: None,
+            rng: Default::default(),
+            recording: None,
+            playback: None,
+        }
+    }
+}
+
+impl Visit for Level {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        visit_since(&mut self.id, "Id", 1, visitor)?;
+        visit_since(&mut self.next_level, "NextLevel", 1, visitor)?;
+        self.scene.visit("Scene", visitor)?;
+        self.map_root.visit("MapRoot", visitor)?;
+        self.player.visit("Player", visitor)?;
+        self.actors.visit("Actors", visitor)?;
+        self.projectiles.visit("Projectiles", visitor)?;
+        self.weapons.visit("Weapons", visitor)?;
+        self.spawn_points.visit("SpawnPoints", visitor)?;
+        if visitor.is_reading() && read_version() < 4 {
+            // Death zones were turned into triggers in version 4.
+            let mut death_zones = Vec::<DeathZone>::new();
+            death_zones.visit("DeathZones", visitor)?;
+            self.triggers = death_zones
+                .into_iter()
+                .map(|zone| Trigger {
+                    bounds: zone.bounds,
+                    ..Trigger::death_zone()
+                })
+                .collect();
+        } else {
+            self.triggers.visit("Triggers", visitor)?;
+        }
+        visit_since(&mut self.level_exits, "LevelExits", 1, visitor)?;
+        visit_since(&mut self.checkpoints, "Checkpoints", 2, visitor)?;
+        visit_since(&mut self.doors, "Doors", 5, visitor)?;
+        visit_since(&mut self.elevators, "Elevators", 5, visitor)?;
+        visit_since(&mut self.director, "Director", 6, visitor)?;
+        visit_since(&mut self.objectives, "Objectives", 7, visitor)?;
+        visit_since(&mut self.stats, "Stats", 8, visitor)?;
+        visit_since(&mut self.respawn_position, "RespawnPosition", 2, visitor)?;
+        visit_since(
+            &mut self.player_respawn_timer,
+            "PlayerRespawnTimer",
+            2,
+            visitor,
+        )?;
+        self.time.visit("Time", visitor)?;
+        visit_since(&mut self.tick, "Tick", 1, visitor)?;
+        self.sound_manager.visit("SoundManager", visitor)?;
+        self.items.visit("Items", visitor)?;
+        self.navmesh.visit("Navmesh", visitor)?;
+        visit_since(&mut self.rng, "Rng", 1, visitor)?;
+
+        if visitor.is_reading() {
+            self.beam = Some(make_beam());
+        }
+
+        visitor.leave_region()
+    }
+}
+
+/// Death zone of saves made before triggers, it is used only to read such saves.
+#[derive(Default)]
+struct DeathZone {
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl Visit for DeathZone {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.bounds.visit("Bounds", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Volume that moves player to the next level when entered.
+#[derive(Default)]
+pub struct LevelExit {
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl Visit for LevelExit {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.bounds.visit("Bounds", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Volume that autosaves the game and becomes player's respawn point when entered.
+#[derive(Default)]
+pub struct Checkpoint {
+    bounds: AxisAlignedBoundingBox,
+    reached: bool,
+}
+
+impl Visit for Checkpoint {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.bounds.visit("Bounds", visitor)?;
+        self.reached.visit("Reached", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+pub struct UpdateContext<'a> {
+    pub time: GameTime,
+    /// Time in seconds since start of the level, see `Level::elapsed`.
+    pub level_time: f64,
+    pub scene: &'a mut Scene,
+    pub items: &'a ItemContainer,
+    pub navmesh: Handle<Navmesh>,
+    pub weapons: &'a WeaponContainer,
+    pub doors: &'a [Door],
+    pub rng: &'a mut GameRng,
+}
+
+#[derive(Default)]
+pub struct AnalysisResult {
+    items: ItemContainer,
+    triggers: Vec<Trigger>,
+    level_exits: Vec<LevelExit>,
+    checkpoints: Vec<Checkpoint>,
+    doors: Vec<Door>,
+    elevators: Vec<Elevator>,
+    spawn_points: Vec<SpawnPoint>,
+    player_spawn_position: Vector3<f32>,
+}
+
+fn make_beam() -> Arc<RwLock<SurfaceSharedData>> {
+    Arc::new(RwLock::new(SurfaceSharedData::make_cylinder(
+        6,
+        0.002,
+        100.0,
+        false,
+        UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 90.0f32.to_radians()).to_homogeneous(),
+    )))
+}
+
+pub async fn analyze(
+    scene: &mut Scene,
+    resource_manager: ResourceManager,
+    sender: Sender<Message>,
+    registry: &EntityRegistry,
+) -> AnalysisResult {
+    let mut result = AnalysisResult::default();
+
+    let LevelLayout {
+        items,
+        spawn_points,
+        triggers,
+        level_exits,
+        checkpoints,
+        doors,
+        elevators,
+        player_spawn_position,
+    } = registry.process(scene);
+
+    for spawn in items {
+        let mut item = Item::new(
+            spawn.kind,
+            spawn.position,
+            scene,
+            resource_manager.clone(),
+            sender.clone(),
+        )
+        .await;
+        item.set_amount(spawn.amount);
+        result.items.add(item);
+    }
+    for (handle, mut trigger) in triggers {
+        let node = &mut scene.graph[handle];
+        node.set_visibility(false);
+        trigger.bounds = node.as_mesh().world_bounding_box();
+        result.triggers.push(trigger);
+    }
+    for handle in level_exits {
+        let node = &mut scene.graph[handle];
+        node.set_visibility(false);
+        result.level_exits.push(LevelExit {
+            bounds: node.as_mesh().world_bounding_box(),
+        });
+    }
+    for handle in checkpoints {
+        let node = &mut scene.graph[handle];
+        node.set_visibility(false);
+        result.checkpoints.push(Checkpoint {
+            bounds: node.as_mesh().world_bounding_box(),
+            reached: false,
+        });
+    }
+    for spawn in doors {
+        result
+            .doors
+            .push(Door::new(spawn.node, spawn.key, spawn.speed, scene));
+    }
+    for spawn in elevators {
+        result
+            .elevators
+            .push(Elevator::new(spawn.node, spawn.height, spawn.speed, scene));
+    }
+    result.spawn_points = spawn_points;
+    result.player_spawn_position = player_spawn_position;
+
+    result
+}
+
+async fn spawn_player(
+    spawn_position: Vector3<f32>,
+    actors: &mut ActorContainer,
+    weapons: &mut WeaponContainer,
+    sender: Sender<Message>,
+    resource_manager: ResourceManager,
+    control_scheme: Arc<RwLock<ControlScheme>>,
+    scene: &mut Scene,
+) -> Handle<Actor> {
+    let player = Player::new(
+        scene,
+        resource_manager.clone(),
+        spawn_position,
+        sender.clone(),
+        control_scheme,
+    )
+    .await;
+    let player = actors.add(Actor::Player(player));
+    actors
+        .get_mut(player)
+        .set_position(&mut scene.physics, spawn_position);
+
+    let weapons_to_give = WeaponKind::starting_weapons();
+    for (i, &weapon) in weapons_to_give.iter().enumerate() {
+        give_new_weapon(
+            weapon,
+            player,
+            sender.clone(),
+            resource_manager.clone(),
+            i == weapons_to_give.len() - 1,
+            weapons,
+            actors,
+            scene,
+        )
+        .await;
+    }
+
+    player
+}
+
+/// Creates a weapon and gives it to an actor, returns false if the weapon can't be created.
+async fn give_new_weapon(
+    kind: WeaponKind,
+    actor: Handle<Actor>,
+    sender: Sender<Message>,
+    resource_manager: ResourceManager,
+    visible: bool,
+    weapons: &mut WeaponContainer,
+    actors: &mut ActorContainer,
+    scene: &mut Scene,
+) -> bool {
+    if actors.contains(actor) {
+        let mut weapon = match Weapon::new(kind, resource_manager, scene, sender.clone()).await {
+            Ok(weapon) => weapon,
+            Err(e) => {
+                Log::writeln(MessageKind::Error, e);
+                return false;
+            }
+        };
+        weapon.set_owner(actor);
+        let weapon_model = weapon.get_model();
+        scene.graph[weapon_model].set_visibility(visible);
+        let actor = actors.get_mut(actor);
+        // New weapon comes with full magazine and some ammo in reserve.
+        actor
+            .inventory
+            .add_ammo(weapon.ammo_kind(), weapon.definition.ammo);
+        let weapon_handle = weapons.add(weapon);
+        actor.add_weapon(weapon_handle);
+        scene.graph.link_nodes(weapon_model, actor.weapon_pivot());
+        true
+    } else {
+        false
+    }
+}
+
+async fn spawn_bot(
+    spawn_point: &mut SpawnPoint,
+    actors: &mut ActorContainer,
+    weapons: &mut WeaponContainer,
+    resource_manager: ResourceManager,
+    sender: Sender<Message>,
+    scene: &mut Scene,
+) -> Handle<Actor> {
+    spawn_point.spawned = true;
+    spawn_point.respawn_timer = 0.0;
+
+    let bot = add_bot(
+        spawn_point.bot_kind,
+        spawn_point.position,
+        actors,
+        resource_manager.clone(),
+        sender.clone(),
+        scene,
+    )
+    .await;
+    if bot.is_none() {
+        return bot;
+    }
+
+    if let Some(health) = spawn_point.health {
+        if let Actor::Bot(bot) = actors.get_mut(bot) {
+            bot.set_health(health);
+        }
+    }
+
+    if let Some(weapon) = spawn_point.weapon {
+        give_new_weapon(
+            weapon,
+            bot,
+            sender,
+            resource_manager,
+            true,
+            weapons,
+            actors,
+            scene,
+        )
+        .await;
+    }
+
+    spawn_point.bot = bot;
+
+    bot
+}
+
+async fn add_bot(
+    kind: BotKind,
+    position: Vector3<f32>,
+    actors: &mut ActorContainer,
+    resource_manager: ResourceManager,
+    sender: Sender<Message>,
+    scene: &mut Scene,
+) -> Handle<Actor> {
+    match Bot::new(
+        kind,
+        resource_manager.clone(),
+        scene,
+        position,
+        sender.clone(),
+    )
+    .await
+    {
+        Ok(bot) => actors.add(Actor::Bot(bot)),
+        Err(e) => {
+            Log::writeln(
+                MessageKind::Error,
+                format!("Unable to spawn bot. Reason: {}", e),
+            );
+            Handle::NONE
+        }
+    }
+}
+
+impl Level {
+    pub async fn new(
+        definition: LevelDefinition,
+        seed: u64,
+        resource_manager: ResourceManager,
+        control_scheme: Arc<RwLock<ControlScheme>>,
+        sender: Sender<Message>,
+    ) -> (Level, Scene) {
+        let mut scene = Scene::new();
+
+        let sound_manager = SoundManager::new(scene.sound_context.clone());
+
+        let (proximity_events_sender, proximity_events_receiver) = crossbeam::channel::unbounded();
+        let (contact_events_sender, contact_events_receiver) = crossbeam::channel::unbounded();
+
+        scene.physics.event_handler = Box::new(ChannelEventCollector::new(
+            proximity_events_sender.clone(),
+            contact_events_sender.clone(),
+        ));
+
+        let map_model = resource_manager
+            .request_model(Path::new(&definition.scene))
+            .await
+            .unwrap();
+
+        // Instantiate map
+        let map_root = map_model.instantiate_geometry(&mut scene);
+
+        let AnalysisResult {
+            items,
+            triggers,
+            level_exits,
+            checkpoints,
+            doors,
+            elevators,
+            mut spawn_points,
+            player_spawn_position,
+        } = analyze(
+            &mut scene,
+            resource_manager.clone(),
+            sender.clone(),
+            &EntityRegistry::default(),
+        )
+        .await;
+        let objectives = ObjectiveContainer::new(&definition.objectives, &mut scene);
+        let mut actors = ActorContainer::new();
+        let mut weapons = WeaponContainer::new();
+
+        // Spawn points of a group are waiting for a trigger.
+        for pt in spawn_points.iter_mut().filter(|pt| pt.group.is_none()) {
+            spawn_bot(
+                pt,
+                &mut actors,
+                &mut weapons,
+                resource_manager.clone(),
+                sender.clone(),
+                &mut scene,
+            )
+            .await;
+        }
+
+        let level = Level {
+            player: spawn_player(
+                player_spawn_position,
+                &mut actors,
+                &mut weapons,
+                sender.clone(),
+                resource_manager.clone(),
+                control_scheme.clone(),
+                &mut scene,
+            )
+            .await,
+            id: definition.id,
+            next_level: definition.next,
+            map_root,
+            actors,
+            weapons,
+            items,
+            triggers,
+            level_exits,
+            checkpoints,
+            doors,
+            elevators,
+            director: EncounterDirector::new(definition.director),
+            objectives,
+            stats: Default::default(),
+            respawn_position: None,
+            player_respawn_timer: 0.0,
+            ended: false,
+            spawn_points,
+            navmesh: scene.navmeshes.handle_from_index(0),
+            scene: Handle::NONE, // Filled when scene will be moved to engine.
+            sender: Some(sender),
+            control_scheme: Some(control_scheme),
+            time: 0.0,
+            tick: 0,
+            contact_events_receiver: Some(contact_events_receiver),
+            proximity_events_receiver: Some(proximity_events_receiver),
+            projectiles: ProjectileContainer::new(),
+            sound_manager,
+            beam: Some(make_beam()),
+            rng: GameRng::new(seed),
+            recording: None,
+            playback: None,
+        };
+
+        (level, scene)
+    }
+
+    pub fn destroy(&mut self, engine: &mut GameEngine) {
+        engine.scenes.remove(self.scene);
+    }
+
+    async fn give_new_weapon(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        actor: Handle<Actor>,
+        kind: WeaponKind,
+    ) -> bool {
+        give_new_weapon(
+            kind,
+            actor,
+            self.sender.clone().unwrap(),
+            resource_manager,
+            true,
+            &mut self.weapons,
+            &mut self.actors,
+            scene,
+        )
+        .await
+    }
+
+    /// Sets identity of the level (its id and next level) from level definition.
+    pub fn assign_definition(&mut self, definition: &LevelDefinition) {
+        self.id = definition.id.clone();
+        self.next_level = definition.next.clone();
+        self.director.settings = definition.director.clone();
+    }
+
+    /// Returns seed of random number generator of the level.
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    pub fn get_player(&self) -> Handle<Actor> {
+        self.player
+    }
+
+    pub fn process_input_event(&mut self, event: &Event<()>, scene: &mut Scene, dt: f32) {
+        // OS input is ignored while replay is playing, otherwise it will break determinism.
+        if self.player.is_some() && self.playback.is_none() {
+            if let Actor::Player(player) = self.actors.get_mut(self.player) {
+                if let Some(input) = player.process_input_event(event, dt, scene) {
+                    if let Some(recording) = self.recording.as_mut() {
+                        recording.push(self.tick, input);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts recording of player's input. Recording must be started right after the level was
+    /// created, otherwise it won't be possible to reproduce the session.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Replay::new(self.id.clone(), self.rng.seed()));
+    }
+
+    /// Stops recording and returns everything recorded so far.
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        self.recording.take()
+    }
+
+    /// Starts playback of a replay. Level must be created with the same seed as the replay.
+    pub fn start_playback(&mut self, replay: Replay) {
+        if replay.level != self.id || replay.seed != self.rng.seed() {
+            Log::writeln(
+                MessageKind::Warning,
+                format!(
+                    "Replay of level {} (seed {}) is played on level {} (seed {})!",
+                    replay.level,
+                    replay.seed,
+                    self.id,
+                    self.rng.seed()
+                ),
+            );
+        }
+        self.playback = Some(ReplayPlayer::new(replay));
+    }
+
+    pub fn is_replay_finished(&self) -> bool {
+        self.playback.as_ref().map_or(true, |p| p.is_finished())
+    }
+
+    fn update_playback(&mut self, scene: &mut Scene) {
+        if let Some(playback) = self.playback.as_mut() {
+            while let Some(input) = playback.next_input(self.tick) {
+                if self.actors.contains(self.player) {
+                    if let Actor::Player(player) = self.actors.get_mut(self.player) {
+                        player.apply_input(input, scene);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn actors(&self) -> &ActorContainer {
+        &self.actors
+    }
+
+    pub fn actors_mut(&mut self) -> &mut ActorContainer {
+        &mut self.actors
+    }
+
+    /// Collects short summary of current state of the level for save browser.
+    pub fn save_metadata(&self) -> SaveMetadata {
+        let (health, weapons) = if self.actors.contains(self.player) {
+            let player = self.actors.get(self.player);
+            let weapons = player
+                .weapons()
+                .iter()
+                .map(|w| self.weapons[*w].definition.display_name.clone())
+                .collect();
+            (player.get_health(), weapons)
+        } else {
+            (0.0, Vec::new())
+        };
+
+        SaveMetadata::now(self.id.clone(), self.time, health, weapons)
+    }
+
+    /// Applies reloaded definitions to live entities.
+    pub fn refresh_definitions(&mut self, scene: &mut Scene) {
+        for weapon in self.weapons.iter_mut() {
+            weapon.refresh_definition();
+        }
+        for actor in self.actors.iter_mut() {
+            match actor {
+                Actor::Bot(bot) => bot.refresh_definition(),
+                Actor::Player(player) => player.refresh_definition(),
+            }
+        }
+        for item in self.items.iter_mut() {
+            item.refresh_definition(&mut scene.graph);
+        }
+        for projectile in self.projectiles.iter_mut() {
+            projectile.refresh_definition();
+        }
+    }
+
+    pub fn weapons(&self) -> &WeaponContainer {
+        &self.weapons
+    }
+
+    pub fn weapons_mut(&mut self) -> &mut WeaponContainer {
+        &mut self.weapons
+    }
+
+    /// Returns time in seconds since start of the level. It is counted in fixed steps, so it
+    /// does not depend on when the level was loaded and gameplay timing must use it instead of
+    /// `GameTime::elapsed`.
+    pub fn elapsed(&self) -> f64 {
+        self.tick as f64 / FIXED_FPS as f64
+    }
+
+    pub fn projectiles(&self) -> &ProjectileContainer {
+        &self.projectiles
+    }
+
+    pub fn items(&self) -> &ItemContainer {
+        &self.items
+    }
+
+    pub fn objectives(&self) -> &ObjectiveContainer {
+        &self.objectives
+    }
+
+    pub fn stats(&self) -> &LevelStats {
+        &self.stats
+    }
+
+    /// Returns seed for the next level. It is taken from generator of this level, so a session
+    /// and its replay stay deterministic through level exits.
+    pub fn next_level_seed(&mut self) -> u64 {
+        self.rng.gen()
+    }
+
+    /// Returns time in seconds spent in the level.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn next_level(&self) -> Option<&str> {
+        self.next_level.as_deref()
+    }
+
+    fn pick(&self, scene: &mut Scene, from: Vector3<f32>, to: Vector3<f32>) -> Vector3<f32> {
+        if let Some(ray) = Ray::from_two_points(&from, &to) {
+            let options = RayCastOptions {
+                ray,
+                max_len: std::f32::MAX,
+                groups: InteractionGroups::all(),
+                sort_results: true,
+            };
+            let mut query_buffer = Vec::default();
+            scene.physics.cast_ray(options, &mut query_buffer);
+            // Sensors like hitboxes of actors are not solid.
+            if let Some(pt) = query_buffer.iter().find(|pt| {
+                scene
+                    .physics
+                    .colliders
+                    .get(pt.collider.into())
+                    .map_or(true, |c| !c.is_sensor())
+            }) {
+                pt.position.coords
+            } else {
+                from
+            }
+        } else {
+            from
+        }
+    }
+
+    fn remove_weapon(&mut self, scene: &mut Scene, weapon: Handle<Weapon>) {
+        for projectile in self.projectiles.iter_mut() {
+            if projectile.owner == weapon {
+                // Reset owner because handle to weapon will be invalid after weapon freed.
+                projectile.owner = Handle::NONE;
+            }
+        }
+        self.weapons[weapon].clean_up(scene);
+        self.weapons.free(weapon);
+    }
+
+    async fn add_bot(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        kind: BotKind,
+        position: Vector3<f32>,
+    ) -> Handle<Actor> {
+        add_bot(
+            kind,
+            position,
+            &mut self.actors,
+            resource_manager,
+            self.sender.clone().unwrap(),
+            scene,
+        )
+        .await
+    }
+
+    async fn remove_actor(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        actor: Handle<Actor>,
+    ) {
+        if self.actors.contains(actor) {
+            let character = self.actors.get(actor);
+
+            // Make sure to remove weapons and drop appropriate items (items will be temporary).
+            let drop_position = character.position(&scene.physics);
+            let weapons = character
+                .weapons()
+                .iter()
+                .copied()
+                .collect::<Vec<Handle<Weapon>>>();
+            for weapon in weapons {
+                // Item id of a definition is checked when the definition is loaded.
+                if let Ok(item_kind) = ItemKind::from_id(self.weapons[weapon].definition.item) {
+                    self.spawn_item(
+                        scene,
+                        resource_manager.clone(),
+                        item_kind,
+                        drop_position,
+                        true,
+                        true,
+                    )
+                    .await;
+                }
+                self.remove_weapon(scene, weapon);
+            }
+
+            self.actors.get_mut(actor).clean_up(scene);
+            self.actors.free(actor);
+
+            if self.player == actor {
+                self.player = Handle::NONE;
+            }
+        }
+    }
+
+    /// Gives item to an actor, returns false if the item doesn't fit into inventory of the actor.
+    async fn give_item(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        actor: Handle<Actor>,
+        kind: ItemKind,
+        amount: Option<u32>,
+    ) -> bool {
+        if !self.actors.contains(actor) {
+            return false;
+        }
+
+        let character = self.actors.get_mut(actor);
+
+        if let Some(weapon_kind) = WeaponKind::from_item(kind) {
+            let weapons = &self.weapons;
+            let existing = character
+                .weapons()
+                .iter()
+                .map(|w| &weapons[*w])
+                .find(|w| w.get_kind() == weapon_kind)
+                .map(|w| (w.ammo_kind(), w.definition.ammo));
+            return match existing {
+                // If actor already has weapon of given kind, then just add ammo to reserve.
+                Some((ammo_kind, ammo)) => character
+                    .inventory
+                    .add_ammo(ammo_kind, amount.unwrap_or(ammo)),
+                // Finally if actor does not have such weapon, give new one to him.
+                None => {
+                    self.give_new_weapon(scene, resource_manager, actor, weapon_kind)
+                        .await
+                }
+            };
+        }
+
+        if let Some(ammo_kind) = AmmoKind::from_item(kind) {
+            return character.inventory.add_ammo(
+                ammo_kind,
+                amount.unwrap_or_else(|| ammo_kind.pickup_amount()),
+            );
+        }
+
+        match kind {
+            ItemKind::Medkit => character
+                .inventory
+                .add_medkit(amount.unwrap_or(MEDKIT_HEALTH)),
+            ItemKind::RedKeycard | ItemKind::GreenKeycard | ItemKind::BlueKeycard => {
+                character.inventory.add_keycard(kind)
+            }
+            ItemKind::Grenade => character.inventory.add_grenades(amount.unwrap_or(1)),
+            ItemKind::ArmorShard => {
+                character.add_armor(amount.unwrap_or(ARMOR_SHARD_AMOUNT) as f32)
+            }
+            ItemKind::ArmorVest => character
+                .upgrade_armor(ArmorTier::Heavy, amount.unwrap_or(ARMOR_VEST_AMOUNT) as f32),
+            // Weapons and ammo are given only by their definitions.
+            _ => {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!("There is no definition that gives item {:?}!", kind),
+                );
+                false
+            }
+        }
+    }
+
+    async fn pickup_item(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        actor: Handle<Actor>,
+        item: Handle<Item>,
+    ) {
+        if self.actors.contains(actor)
+            && self.items.contains(item)
+            && self.items.get(item).is_active()
+        {
+            let kind = self.items.get(item).get_kind();
+            let amount = self.items.get(item).amount();
+            if !self
+                .give_item(scene, resource_manager, actor, kind, amount)
+                .await
+            {
+                // Item is left on the ground when there is no room for it.
+                return;
+            }
+
+            let position = self.items.get(item).position(&scene.graph);
+            if self.items.get(item).is_temporary() {
+                self.items.remove(item, &mut scene.graph);
+            } else {
+                self.items.get_mut(item).deactivate(&mut scene.graph);
+            }
+            if actor == self.player {
+                self.stats.items_collected += 1;
+                self.objectives.item_picked_up(item, kind);
+            }
+            self.sender
+                .as_ref()
+                .unwrap()
+                .send(Message::PlaySound {
+                    path: PathBuf::from("data/sounds/item_pickup.ogg"),
+                    position,
+                    gain: 1.0,
+                    rolloff_factor: 3.0,
+                    radius: 2.0,
+                })
+                .unwrap();
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn create_projectile(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        kind: ProjectileKind,
+        position: Vector3<f32>,
+        direction: Vector3<f32>,
+        initial_velocity: Vector3<f32>,
+        owner: Handle<Weapon>,
+        who: Handle<Actor>,
+    ) {
+        let projectile = Projectile::new(
+            kind,
+            resource_manager,
+            scene,
+            direction,
+            position,
+            owner,
+            who,
+            initial_velocity,
+            self.sender.as_ref().unwrap().clone(),
+            &mut self.rng,
+        )
+        .await;
+        self.projectiles.add(projectile);
+    }
+
+    async fn shoot_weapon(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        weapon_handle: Handle<Weapon>,
+        direction: Option<Vector3<f32>>,
+    ) {
+        if self.weapons.contains(weapon_handle) {
+            // Empty weapon is reloaded automatically.
+            if self.weapons[weapon_handle].ammo() == 0 {
+                self.reload_weapon(weapon_handle);
+            }
+            let owner = self.weapons[weapon_handle].owner();
+            let spread_factor = if self.actors.contains(owner) {
+                self.actors.get(owner).spread_factor(&scene.physics)
+            } else {
+                1.0
+            };
+            let level_time = self.elapsed();
+            let weapon = &mut self.weapons[weapon_handle];
+            let shot = weapon.try_shoot(
+                weapon_handle,
+                scene,
+                level_time,
+                resource_manager,
+                direction,
+                spread_factor,
+                &mut self.rng,
+            );
+            if shot && self.actors.contains(owner) {
+                let definition = weapon.definition.clone();
+                if let Actor::Player(player) = self.actors.get_mut(owner) {
+                    let yaw = self.rng.gen_range(-1.0f32..=1.0) * definition.recoil_yaw;
+                    player.apply_recoil(definition.recoil_pitch, yaw);
+                }
+                if owner == self.player {
+                    self.stats.shots_fired += 1;
+                }
+            }
+        }
+    }
+
+    /// Starts reload of a weapon if its owner has ammo in reserve.
+    fn reload_weapon(&mut self, weapon_handle: Handle<Weapon>) {
+        if self.weapons.contains(weapon_handle) {
+            let weapon = &mut self.weapons[weapon_handle];
+            let owner = weapon.owner();
+            if weapon.can_reload() && self.actors.contains(owner) {
+                let amount = self
+                    .actors
+                    .get_mut(owner)
+                    .inventory
+                    .take_ammo(weapon.ammo_kind(), weapon.magazine_size() - weapon.ammo());
+                if amount > 0 {
+                    weapon.start_reload(amount);
+                }
+            }
+        }
+    }
+
+    fn show_weapon(&mut self, scene: &mut Scene, weapon_handle: Handle<Weapon>, state: bool) {
+        self.weapons[weapon_handle].set_visibility(state, &mut scene.graph)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn damage_actor(
+        &mut self,
+        scene: &Scene,
+        actor_handle: Handle<Actor>,
+        who: Handle<Actor>,
+        amount: f32,
+        damage_type: DamageType,
+        position: Option<Vector3<f32>>,
+        location: Option<HitLocation>,
+    ) {
+        if self.actors.contains(actor_handle)
+            && (who.is_none() || who.is_some() && self.actors.contains(who))
+        {
+            let who_position = if who.is_some() {
+                Some(self.actors.get(who).position(&scene.physics))
+            } else {
+                None
+            };
+            let actor = self.actors.get_mut(actor_handle);
+            if actor.is_dead() {
+                return;
+            }
+            if let Actor::Bot(bot) = actor {
+                if let Some(who_position) = who_position {
+                    bot.set_target(actor_handle, who_position);
+                }
+            }
+
+            let (effect, sound) = match damage_type {
+                DamageType::Bullet | DamageType::Melee => (
+                    Some(EffectKind::BloodSpray),
+                    Some("data/sounds/bullet_impact_body.ogg"),
+                ),
+                DamageType::Plasma => (
+                    Some(EffectKind::PlasmaBurn),
+                    Some("data/sounds/bullet_impact_metal.ogg"),
+                ),
+                // Explosions and hazards have their own sounds.
+                DamageType::Explosion | DamageType::Environment => {
+                    (Some(EffectKind::BloodSpray), None)
+                }
+                DamageType::Fall => (None, None),
+            };
+            // Direct hit to the head bursts instead of bleeding.
+            let effect = if location == Some(HitLocation::Head) {
+                effect.map(|_| EffectKind::Headshot)
+            } else {
+                effect
+            };
+            let hit_position = position.unwrap_or_else(|| actor.position(&scene.physics));
+            let sender = self.sender.as_ref().unwrap();
+            if let Some(kind) = effect {
+                // Blood sprays towards the attacker.
+                let orientation = who_position
+                    .and_then(|p| (p - hit_position).try_normalize(std::f32::EPSILON))
+                    .map_or_else(UnitQuaternion::identity, |d| {
+                        UnitQuaternion::face_towards(&d, &Vector3::y())
+                    });
+                sender
+                    .send(Message::CreateEffect {
+                        kind,
+                        position: hit_position,
+                        orientation,
+                    })
+                    .unwrap();
+            }
+            if let Some(sound) = sound {
+                sender
+                    .send(Message::PlaySound {
+                        path: PathBuf::from(sound),
+                        position: hit_position,
+                        gain: 1.0,
+                        rolloff_factor: 4.0,
+                        radius: 3.0,
+                    })
+                    .unwrap();
+            }
+
+            let durability = |a: &Actor| a.get_health().max(0.0) + a.get_armor();
+            let durability_before = durability(actor);
+            let amount = amount * actor.resistances().multiplier(damage_type);
+            actor.damage(amount, damage_type.bypasses_armor());
+
+            if actor_handle == self.player {
+                self.stats.damage_taken += durability_before - durability(actor);
+            } else if who == self.player && who.is_some() {
+                if let Actor::Bot(bot) = actor {
+                    if bot.is_dead() {
+                        self.stats.add_kill(bot.kind());
+                    }
+                }
+            }
+        }
+    }
+
+    async fn spawn_item(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        kind: ItemKind,
+        position: Vector3<f32>,
+        adjust_height: bool,
+        temporary: bool,
+    ) {
+        let position = if adjust_height {
+            self.pick(scene, position, position - Vector3::new(0.0, 1000.0, 0.0))
+        } else {
+            position
+        };
+        let mut item = Item::new(
+            kind,
+            position,
+            scene,
+            resource_manager,
+            self.sender.as_ref().unwrap().clone(),
+        )
+        .await;
+        if temporary {
+            item.make_temporary();
+        }
+        self.items.add(item);
+    }
+
+    fn update_triggers(&mut self, scene: &Scene, dt: f32) {
+        for trigger in self.triggers.iter_mut() {
+            trigger.update(&self.actors, scene, dt, self.sender.as_ref().unwrap());
+        }
+    }
+
+    fn update_objectives(&mut self, scene: &Scene, dt: f32) {
+        self.objectives.update(
+            scene,
+            &self.actors,
+            self.player,
+            dt,
+            self.sender.as_ref().unwrap(),
+        );
+    }
+
+    fn update_director(&mut self, scene: &Scene, dt: f32) {
+        self.director.update(
+            &mut self.spawn_points,
+            &self.actors,
+            self.player,
+            &self.weapons,
+            scene,
+            dt,
+            self.sender.as_ref().unwrap(),
+        );
+    }
+
+    /// Ends the level with victory, results screen will offer to move to the next level.
+    fn end_level(&mut self) {
+        if self.ended {
+            return;
+        }
+        self.ended = true;
+        self.sender
+            .as_ref()
+            .unwrap()
+            .send(Message::EndMatch {
+                outcome: Outcome::Victory,
+            })
+            .unwrap();
+    }
+
+    fn update_level_exits(&mut self, scene: &Scene) {
+        if self.player.is_none() {
+            return;
+        }
+
+        let position = self.actors.get(self.player).position(&scene.physics);
+        if self
+            .level_exits
+            .iter()
+            .any(|exit| exit.bounds.is_contains_point(position))
+        {
+            self.end_level();
+        }
+    }
+
+    fn update_checkpoints(&mut self, scene: &Scene) {
+        if self.player.is_none() || self.actors.get(self.player).is_dead() {
+            return;
+        }
+
+        let position = self.actors.get(self.player).position(&scene.physics);
+        for checkpoint in self.checkpoints.iter_mut() {
+            if !checkpoint.reached && checkpoint.bounds.is_contains_point(position) {
+                checkpoint.reached = true;
+                self.respawn_position = Some(checkpoint.bounds.center());
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::Autosave)
+                    .unwrap();
+            }
+        }
+    }
+
+    fn update_doors(&mut self, scene: &mut Scene, dt: f32) {
+        for door in self.doors.iter_mut() {
+            door.update(scene, &self.actors, dt);
+        }
+        for elevator in self.elevators.iter_mut() {
+            elevator.update(scene, dt);
+        }
+    }
+
+    fn update_use_request(&mut self, scene: &Scene) {
+        if self.actors.contains(self.player) {
+            let requested = match self.actors.get_mut(self.player) {
+                Actor::Player(player) => player.take_use_request(),
+                Actor::Bot(_) => false,
+            };
+            if requested {
+                self.use_object(scene, self.player);
+            }
+        }
+    }
+
+    /// Opens or closes nearest door, or moves nearest elevator.
+    fn use_object(&mut self, scene: &Scene, actor: Handle<Actor>) {
+        let character = self.actors.get(actor);
+        let position = character.position(&scene.physics);
+
+        if let Some(door) = self
+            .doors
+            .iter_mut()
+            .find(|door| door.is_near(position, USE_DISTANCE))
+        {
+            if door.can_be_opened_by(character) {
+                door.toggle();
+            } else if let Some(key) = door.key() {
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::ShowText {
+                        text: format!("{} is required", key.description()),
+                    })
+                    .unwrap();
+            }
+        } else if let Some(elevator) = self
+            .elevators
+            .iter_mut()
+            .find(|elevator| elevator.is_near(position, USE_DISTANCE))
+        {
+            elevator.toggle();
+        }
+    }
+
+    fn update_game_ending(&mut self, dt: f32) {
+        if self.actors.get(self.player).is_dead() {
+            if self.respawn_position.is_some() {
+                self.player_respawn_timer += dt;
+                if self.player_respawn_timer >= RESPAWN_TIME {
+                    self.player_respawn_timer = 0.0;
+                    self.sender
+                        .as_ref()
+                        .unwrap()
+                        .send(Message::RespawnPlayer)
+                        .unwrap();
+                }
+            } else {
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::EndMatch {
+                        outcome: Outcome::Defeat,
+                    })
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Replaces dead player with a new one at last reached checkpoint.
+    async fn respawn_player(&mut self, scene: &mut Scene, resource_manager: ResourceManager) {
+        if let Some(position) = self.respawn_position {
+            // Weapons of dead player are dropped as usual.
+            self.remove_actor(scene, resource_manager.clone(), self.player)
+                .await;
+
+            self.player = spawn_player(
+                position,
+                &mut self.actors,
+                &mut self.weapons,
+                self.sender.clone().unwrap(),
+                resource_manager,
+                self.control_scheme.clone().unwrap(),
+                scene,
+            )
+            .await;
+        }
+    }
+
+    pub fn update(&mut self, scene: &mut Scene, time: GameTime) {
+        self.update_playback(scene);
+        self.time += time.delta;
+        while let Ok(proximity_event) = self.proximity_events_receiver.as_ref().unwrap().try_recv()
+        {
+            for proj in self.projectiles.iter_mut() {
+                proj.handle_proximity(&proximity_event, scene, &self.actors, &self.weapons);
+            }
+        }
+        self.update_triggers(scene, time.delta);
+        self.update_director(scene, time.delta);
+        self.update_objectives(scene, time.delta);
+        self.update_level_exits(scene);
+        self.update_checkpoints(scene);
+        self.update_use_request(scene);
+        self.update_doors(scene, time.delta);
+        self.items.update(scene, time.delta);
+        self.weapons.update(scene, time.delta);
+        self.projectiles
+            .update(scene, &self.actors, &self.weapons, time);
+        let level_time = self.elapsed();
+        let mut ctx = UpdateContext {
+            time,
+            level_time,
+            scene,
+            items: &self.items,
+            navmesh: self.navmesh,
+            weapons: &self.weapons,
+            doors: &self.doors,
+            rng: &mut self.rng,
+        };
+        self.actors.update(&mut ctx);
+        self.update_game_ending(time.delta);
+        self.tick += 1;
+    }
+
+    pub async fn handle_message(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        message: &Message,
+    ) {
+        self.sound_manager
+            .handle_message(resource_manager.clone(), &message)
+            .await;
+
+        match message {
+            &Message::GiveNewWeapon { actor, kind } => {
+                self.give_new_weapon(scene, resource_manager, actor, kind)
+                    .await;
+            }
+            Message::AddBot { kind, position } => {
+                self.add_bot(scene, resource_manager, *kind, *position)
+                    .await;
+            }
+            &Message::RemoveActor { actor } => {
+                self.remove_actor(scene, resource_manager, actor).await
+            }
+            Message::RespawnPlayer => self.respawn_player(scene, resource_manager).await,
+            &Message::GiveItem { actor, kind } => {
+                self.give_item(scene, resource_manager, actor, kind, None)
+                    .await;
+            }
+            &Message::PickUpItem { actor, item } => {
+                self.pickup_item(scene, resource_manager, actor, item).await;
+            }
+            &Message::ReloadWeapon { weapon } => self.reload_weapon(weapon),
+            &Message::ShootWeapon { weapon, direction } => {
+                self.shoot_weapon(scene, resource_manager, weapon, direction)
+                    .await
+            }
+            &Message::CreateProjectile {
+                kind,
+                position,
+                direction,
+                initial_velocity,
+                owner,
+                who,
+            } => {
+                self.create_projectile(
+                    scene,
+                    resource_manager,
+                    kind,
+                    position,
+                    direction,
+                    initial_velocity,
+                    owner,
+                    who,
+                )
+                .await
+            }
+            &Message::ShowWeapon { weapon, state } => self.show_weapon(scene, weapon, state),
+            &Message::SpawnBot { spawn_point_id } => {
+                if let Some(spawn_point) = self.spawn_points.get_mut(spawn_point_id) {
+                    spawn_bot(
+                        spawn_point,
+                        &mut self.actors,
+                        &mut self.weapons,
+                        resource_manager,
+                        self.sender.clone().unwrap(),
+                        scene,
+                    )
+                    .await;
+                }
+            }
+            Message::SpawnBots { group } => {
+                self.director.start_wave(&self.spawn_points, group);
+            }
+            Message::EndLevel | Message::ObjectivesCompleted => self.end_level(),
+            &Message::DamageActor {
+                actor,
+                who,
+                amount,
+                damage_type,
+                position,
+                location,
+            } => {
+                self.damage_actor(scene, actor, who, amount, damage_type, position, location);
+            }
+            &Message::ShotHit { who, location } => {
+                if who == self.player && who.is_some() {
+                    self.stats.shots_hit += 1;
+                    if location == Some(HitLocation::Head) {
+                        self.stats.headshots += 1;
+                    }
+                }
+            }
+            &Message::CreateExplosion {
+                position,
+                normal,
+                radius,
+                damage,
+                who,
+            } => {
+                let explosion = Explosion {
+                    position,
+                    normal,
+                    radius,
+                    damage,
+                    who,
+                };
+                explosion.apply(scene, &mut self.actors, self.sender.as_ref().unwrap());
+            }
+            &Message::CreateEffect {
+                kind,
+                position,
+                orientation,
+            } => {
+                effects::create(
+                    kind,
+                    &mut scene.graph,
+                    resource_manager,
+                    position,
+                    orientation,
+                );
+            }
+            &Message::SpawnItem {
+                kind,
+                position,
+                adjust_height,
+            } => {
+                self.spawn_item(
+                    scene,
+                    resource_manager,
+                    kind,
+                    position,
+                    adjust_height,
+                    false,
+                )
+                .await
+            }
+            Message::ShootRay {
+                weapon,
+                begin,
+                end,
+                damage,
+                impact_sound,
+            } => {
+                MeshBuilder::new(
+                    BaseBuilder::new().with_lifetime(0.7).with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(*begin)
+                            .with_local_rotation(UnitQuaternion::face_towards(
+                                &(end - begin),
+                                &Vector3::y(),
+                            ))
+                            .build(),
+                    ),
+                )
+                .with_surfaces(vec![SurfaceBuilder::new(self.beam.clone().unwrap())
+                    .with_color(Color::from_rgba(255, 127, 40, 120))
+                    .build()])
+                .with_cast_shadows(false)
+                .with_render_path(RenderPath::Forward)
+                .build(&mut scene.graph);
+
+                // Do immediate intersection test and solve it.
+                if let Some(hit) = ray_hit(
+                    *begin,
+                    *end,
+                    *weapon,
+                    &self.weapons,
+                    &self.actors,
+                    &mut scene.physics,
+                    Default::default(),
+                ) {
+                    // Just send new messages, instead of doing everything manually here.
+                    // Effects of a hit actor are created when the actor is damaged.
+                    if hit.actor.is_some() {
+                        self.sender
+                            .as_ref()
+                            .unwrap()
+                            .send(Message::ShotHit {
+                                who: hit.who,
+                                location: hit.location,
+                            })
+                            .unwrap();
+                    } else {
+                        self.sender
+                            .as_ref()
+                            .unwrap()
+                            .send(Message::CreateEffect {
+                                kind: EffectKind::BulletImpact,
+                                position: hit.position,
+                                orientation: UnitQuaternion::face_towards(
+                                    &hit.normal,
+                                    &Vector3::y(),
+                                ),
+                            })
+                            .unwrap();
+
+                        self.sender
+                            .as_ref()
+                            .unwrap()
+                            .send(Message::PlaySound {
+                                path: impact_sound.clone(),
+                                position: hit.position,
+                                gain: 1.0,
+                                rolloff_factor: 4.0,
+                                radius: 3.0,
+                            })
+                            .unwrap();
+                    }
+
+                    self.sender
+                        .as_ref()
+                        .unwrap()
+                        .send(Message::DamageActor {
+                            actor: hit.actor,
+                            who: hit.who,
+                            amount: *damage * hit.damage_multiplier,
+                            damage_type: DamageType::Bullet,
+                            position: Some(hit.position),
+                            location: hit.location,
+                        })
+                        .unwrap();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn set_message_sender(&mut self, sender: Sender<Message>, engine: &mut GameEngine) {
+        self.sender = Some(sender.clone());
+
+        // Attach new sender to all event sources.
+        for actor in self.actors.iter_mut() {
+            actor.sender = Some(sender.clone());
+        }
+        for weapon in self.weapons.iter_mut() {
+            weapon.sender = Some(sender.clone());
+        }
+        for projectile in self.projectiles.iter_mut() {
+            projectile.sender = Some(sender.clone());
+        }
+        for item in self.items.iter_mut() {
+            item.sender = Some(sender.clone());
+        }
+
+        let (proximity_events_sender, proximity_events_receiver) = crossbeam::channel::unbounded();
+        let (contact_events_sender, contact_events_receiver) = crossbeam::channel::unbounded();
+
+        self.proximity_events_receiver = Some(proximity_events_receiver);
+        self.contact_events_receiver = Some(contact_events_receiver);
+
+        engine.scenes[self.scene].physics.event_handler = Box::new(ChannelEventCollector::new(
+            proximity_events_sender.clone(),
+            contact_events_sender.clone(),
+        ));
+    }
+
+    pub fn debug_draw(&self, engine: &mut GameEngine) {
+        let scene = &mut engine.scenes[self.scene];
+
+        let drawing_context = &mut scene.drawing_context;
+
+        drawing_context.clear_lines();
+
+        scene.physics.draw(drawing_context);
+
+        if self.navmesh.is_some() {
+            let navmesh = &scene.navmeshes[self.navmesh];
+
+            for pt in navmesh.vertices() {
+                for neighbour in pt.neighbours() {
+                    drawing_context.add_line(scene::Line {
+                        begin: pt.position(),
+                        end: navmesh.vertices()[*neighbour as usize].position(),
+                        color: Default::default(),
+                    });
+                }
+            }
+
+            for actor in self.actors.iter() {
+                if let Actor::Bot(bot) = actor {
+                    bot.debug_draw(drawing_context);
+                }
+            }
+        }
+
+        for trigger in self.triggers.iter() {
+            drawing_context.draw_aabb(&trigger.bounds, Color::opaque(0, 0, 200));
+        }
+
+        for level_exit in self.level_exits.iter() {
+            drawing_context.draw_aabb(&level_exit.bounds, Color::opaque(0, 200, 0));
+        }
+
+        for checkpoint in self.checkpoints.iter() {
+            drawing_context.draw_aabb(&checkpoint.bounds, Color::opaque(0, 100, 200));
+        }
+
+        for door in self.doors.iter() {
+            drawing_context.draw_aabb(door.bounds(), Color::opaque(200, 200, 0));
+        }
+
+        for elevator in self.elevators.iter() {
+            drawing_context.draw_aabb(&elevator.bounds(), Color::opaque(200, 100, 0));
+        }
+    }
+}
+
+pub struct SpawnPoint {
+    position: Vector3<f32>,
+    bot_kind: BotKind,
+    spawned: bool,
+    /// Overrides health from bot definition.
+    health: Option<f32>,
+    /// Weapon that will be given to a bot.
+    weapon: Option<WeaponKind>,
+    /// Spawn points of a group are spawned by triggers instead of at level start.
+    group: Option<String>,
+    /// Bot will be respawned after this amount of seconds since its death.
+    respawn_time: Option<f32>,
+    respawn_timer: f32,
+    /// Last bot spawned at this point.
+    bot: Handle<Actor>,
+}
+
+impl Default for SpawnPoint {
+    fn default() -> Self {
+        Self {
+            position: Default::default(),
+            bot_kind: Default::default(),
+            spawned: false,
+            health: None,
+            weapon: None,
+            group: None,
+            respawn_time: None,
+            respawn_timer: 0.0,
+            bot: Handle::NONE,
+        }
+    }
+}
+
+impl Visit for SpawnPoint {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.position.visit("Position", visitor)?;
+        self.spawned.visit("Spawned", visitor)?;
+
+        let mut kind_id = self.bot_kind.id();
+        kind_id.visit("BotKind", visitor)?;
+        if visitor.is_reading() {
+            self.bot_kind = BotKind::from_id(kind_id)?;
+        }
+
+        visit_since(&mut self.health, "Health", 3, visitor)?;
+
+        let mut weapon_id = self.weapon.map(|w| w.id());
+        visit_since(&mut weapon_id, "Weapon", 3, visitor)?;
+        if visitor.is_reading() {
+            self.weapon = weapon_id.map(WeaponKind::new).transpose()?;
+        }
+
+        visit_since(&mut self.group, "Group", 4, visitor)?;
+        visit_since(&mut self.respawn_time, "RespawnTime", 6, visitor)?;
+        visit_since(&mut self.respawn_timer, "RespawnTimer", 6, visitor)?;
+        visit_since(&mut self.bot, "Bot", 6, visitor)?;
+
+        visitor.leave_region()
+    }
+}
@@ -0,0 +1,360 @@
+//! Objectives are goals of a level, they're declared in level manifest and completed one by
+//! one. Areas of objectives are meshes of level scene referenced by name, they're hidden when
+//! a level is created.
+
+use crate::{
+    actor::{Actor, ActorContainer},
+    item::{Item, ItemKind},
+    message::Message,
+    save::visit_since,
+};
+use rg3d::{
+    core::{
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    scene::{node::Node, Scene},
+    utils::log::{Log, MessageKind},
+};
+use serde::Deserialize;
+use std::sync::mpsc::Sender;
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum ObjectiveKindDefinition {
+    KillAllInArea { area: String },
+    ReachLocation { area: String },
+    CollectItem { item: ItemKind, count: u32 },
+    Survive { seconds: f32 },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ObjectiveDefinition {
+    pub description: String,
+    pub kind: ObjectiveKindDefinition,
+}
+
+pub enum ObjectiveKind {
+    KillAllInArea {
+        bounds: AxisAlignedBoundingBox,
+        /// Whether a living bot was in the area, empty area is not cleared until bots arrive.
+        occupied: bool,
+    },
+    ReachLocation {
+        bounds: AxisAlignedBoundingBox,
+    },
+    CollectItem {
+        item: ItemKind,
+        count: u32,
+        /// Items that were picked up by the player.
+        collected: Vec<Handle<Item>>,
+    },
+    Survive {
+        duration: f32,
+        elapsed: f32,
+    },
+}
+
+impl Default for ObjectiveKind {
+    fn default() -> Self {
+        ObjectiveKind::Survive {
+            duration: 0.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl ObjectiveKind {
+    fn id(&self) -> u32 {
+        match self {
+            ObjectiveKind::KillAllInArea { .. } => 0,
+            ObjectiveKind::ReachLocation { .. } => 1,
+            ObjectiveKind::CollectItem { .. } => 2,
+            ObjectiveKind::Survive { .. } => 3,
+        }
+    }
+
+    fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(ObjectiveKind::KillAllInArea {
+                bounds: Default::default(),
+                occupied: false,
+            }),
+            1 => Ok(ObjectiveKind::ReachLocation {
+                bounds: Default::default(),
+            }),
+            2 => Ok(ObjectiveKind::CollectItem {
+                item: ItemKind::Medkit,
+                count: 0,
+                collected: Default::default(),
+            }),
+            3 => Ok(ObjectiveKind::Survive {
+                duration: 0.0,
+                elapsed: 0.0,
+            }),
+            _ => Err(format!("Invalid objective kind {}", id)),
+        }
+    }
+}
+
+impl Visit for ObjectiveKind {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = Self::from_id(id)?;
+        }
+
+        match self {
+            ObjectiveKind::KillAllInArea { bounds, occupied } => {
+                bounds.visit("Bounds", visitor)?;
+                visit_since(occupied, "Occupied", 16, visitor)?;
+            }
+            ObjectiveKind::ReachLocation { bounds } => {
+                bounds.visit("Bounds", visitor)?;
+            }
+            ObjectiveKind::CollectItem {
+                item,
+                count,
+                collected,
+            } => {
+                let mut item_id = item.id();
+                item_id.visit("Item", visitor)?;
+                if visitor.is_reading() {
+                    *item = ItemKind::from_id(item_id)?;
+                }
+                count.visit("Count", visitor)?;
+                collected.visit("Collected", visitor)?;
+            }
+            ObjectiveKind::Survive { duration, elapsed } => {
+                duration.visit("Duration", visitor)?;
+                elapsed.visit("Elapsed", visitor)?;
+            }
+        }
+
+        visitor.leave_region()
+    }
+}
+
+#[derive(Default)]
+pub struct Objective {
+    description: String,
+    kind: ObjectiveKind,
+    completed: bool,
+}
+
+impl Visit for Objective {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.description.visit("Description", visitor)?;
+        self.kind.visit("Kind", visitor)?;
+        self.completed.visit("Completed", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl Objective {
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Returns description of the objective with its progress.
+    pub fn status(&self) -> String {
+        match &self.kind {
+            ObjectiveKind::CollectItem {
+                count, collected, ..
+            } => format!("{} ({}/{})", self.description, collected.len(), count),
+            ObjectiveKind::Survive { duration, elapsed } if !self.completed => format!(
+                "{} ({} s)",
+                self.description,
+                (duration - elapsed).max(0.0).ceil()
+            ),
+            _ => self.description.clone(),
+        }
+    }
+}
+
+/// Looks for a mesh with given name and returns its bounds, the mesh is hidden.
+fn find_area(scene: &mut Scene, area: &str) -> Option<AxisAlignedBoundingBox> {
+    let root = scene.graph.get_root();
+    let handle = scene.graph.find_by_name(root, area);
+    if handle.is_none() {
+        return None;
+    }
+    let node = &mut scene.graph[handle];
+    if let Node::Mesh(_) = node {
+        node.set_visibility(false);
+        Some(node.as_mesh().world_bounding_box())
+    } else {
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct ObjectiveContainer {
+    objectives: Vec<Objective>,
+    /// Index of objective that is being tracked, objectives are completed in order.
+    current: u32,
+}
+
+impl Visit for ObjectiveContainer {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.objectives.visit("Objectives", visitor)?;
+        self.current.visit("Current", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl ObjectiveContainer {
+    pub fn new(definitions: &[ObjectiveDefinition], scene: &mut Scene) -> Self {
+        let mut objectives = Vec::new();
+
+        for definition in definitions {
+            let kind = match &definition.kind {
+                ObjectiveKindDefinition::KillAllInArea { area }
+                | ObjectiveKindDefinition::ReachLocation { area } => match find_area(scene, area) {
+                    Some(bounds) => {
+                        if let ObjectiveKindDefinition::KillAllInArea { .. } = definition.kind {
+                            ObjectiveKind::KillAllInArea {
+                                bounds,
+                                occupied: false,
+                            }
+                        } else {
+                            ObjectiveKind::ReachLocation { bounds }
+                        }
+                    }
+                    None => {
+                        Log::writeln(
+                            MessageKind::Warning,
+                            format!(
+                                "Objective {}: there is no mesh {}, objective is skipped!",
+                                definition.description, area
+                            ),
+                        );
+                        continue;
+                    }
+                },
+                &ObjectiveKindDefinition::CollectItem { item, count } => {
+                    ObjectiveKind::CollectItem {
+                        item,
+                        count,
+                        collected: Default::default(),
+                    }
+                }
+                &ObjectiveKindDefinition::Survive { seconds } => ObjectiveKind::Survive {
+                    duration: seconds,
+                    elapsed: 0.0,
+                },
+            };
+
+            objectives.push(Objective {
+                description: definition.description.clone(),
+                kind,
+                completed: false,
+            });
+        }
+
+        Self {
+            objectives,
+            current: 0,
+        }
+    }
+
+    /// Returns objectives that were completed or are in progress.
+    pub fn visible(&self) -> impl Iterator<Item = &Objective> {
+        self.objectives.iter().take(self.current as usize + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objectives.is_empty()
+    }
+
+    /// Returns true when every objective is completed.
+    pub fn is_completed(&self) -> bool {
+        self.objectives.iter().all(|o| o.completed)
+    }
+
+    fn current_mut(&mut self) -> Option<&mut Objective> {
+        self.objectives.get_mut(self.current as usize)
+    }
+
+    fn complete_current(&mut self, sender: &Sender<Message>) {
+        if let Some(objective) = self.current_mut() {
+            objective.completed = true;
+            let text = format!("Objective complete: {}", objective.description);
+            sender.send(Message::ShowText { text }).unwrap();
+            self.current += 1;
+            if self.is_completed() {
+                sender.send(Message::ObjectivesCompleted).unwrap();
+            }
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        scene: &Scene,
+        actors: &ActorContainer,
+        player: Handle<Actor>,
+        dt: f32,
+        sender: &Sender<Message>,
+    ) {
+        if !actors.contains(player) || actors.get(player).is_dead() {
+            return;
+        }
+        let player_position = actors.get(player).position(&scene.physics);
+
+        let completed = match self.current_mut() {
+            Some(objective) => match &mut objective.kind {
+                ObjectiveKind::KillAllInArea { bounds, occupied } => {
+                    let has_bots = actors.iter().any(|actor| {
+                        matches!(actor, Actor::Bot(_))
+                            && !actor.is_dead()
+                            && bounds.is_contains_point(actor.position(&scene.physics))
+                    });
+                    *occupied |= has_bots;
+                    *occupied && !has_bots
+                }
+                ObjectiveKind::ReachLocation { bounds } => {
+                    bounds.is_contains_point(player_position)
+                }
+                ObjectiveKind::CollectItem {
+                    count, collected, ..
+                } => collected.len() as u32 >= *count,
+                ObjectiveKind::Survive { duration, elapsed } => {
+                    *elapsed += dt;
+                    *elapsed >= *duration
+                }
+            },
+            None => false,
+        };
+
+        if completed {
+            self.complete_current(sender);
+        }
+    }
+
+    /// Counts item picked up by the player.
+    pub fn item_picked_up(&mut self, item: Handle<Item>, kind: ItemKind) {
+        if let Some(Objective {
+            kind:
+                ObjectiveKind::CollectItem {
+                    item: required,
+                    collected,
+                    ..
+                },
+            ..
+        }) = self.current_mut()
+        {
+            if *required == kind {
+                collected.push(item);
+            }
+        }
+    }
+}
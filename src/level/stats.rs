@@ -0,0 +1,76 @@
+//! Statistics of the player in a level, they're shown on results screen when the level ends.
+
+use crate::{bot::BotKind, save::visit_since};
+use rg3d::core::visitor::{Visit, VisitResult, Visitor};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// Player has reached the end of the level or completed every objective.
+    Victory,
+    /// Player has died and there is no checkpoint to respawn at.
+    Defeat,
+}
+
+impl Outcome {
+    pub fn description(self) -> &'static str {
+        match self {
+            Outcome::Victory => "Mission Complete",
+            Outcome::Defeat => "Mission Failed",
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct LevelStats {
+    /// Amount of bots killed by the player, indexed by id of bot kind.
+    kills: Vec<u32>,
+    pub shots_fired: u32,
+    pub shots_hit: u32,
+    /// Hits of the player to heads of actors.
+    pub headshots: u32,
+    pub damage_taken: f32,
+    pub items_collected: u32,
+}
+
+impl Visit for LevelStats {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.kills.visit("Kills", visitor)?;
+        self.shots_fired.visit("ShotsFired", visitor)?;
+        self.shots_hit.visit("ShotsHit", visitor)?;
+        visit_since(&mut self.headshots, "Headshots", 14, visitor)?;
+        self.damage_taken.visit("DamageTaken", visitor)?;
+        self.items_collected.visit("ItemsCollected", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl LevelStats {
+    pub fn add_kill(&mut self, kind: BotKind) {
+        let index = kind.id() as usize;
+        if self.kills.len() <= index {
+            self.kills.resize(index + 1, 0);
+        }
+        self.kills[index] += 1;
+    }
+
+    /// Returns kinds of killed bots with amount of kills.
+    pub fn kills(&self) -> impl Iterator<Item = (BotKind, u32)> + '_ {
+        self.kills
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .filter_map(|(id, &count)| BotKind::from_id(id as i32).ok().map(|kind| (kind, count)))
+    }
+
+    /// Returns ratio of shots that hit an actor.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.shots_hit as f32 / self.shots_fired as f32
+        }
+    }
+}
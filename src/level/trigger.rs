@@ -0,0 +1,388 @@
+//! Triggers are invisible volumes that react on actors: when an actor enters, leaves or stays
+//! inside of a volume, trigger sends messages that are described by its actions. Triggers are
+//! created from `Trigger` markers of a level, `DeathZone` marker is a trigger too.
+
+use crate::{
+    actor::{Actor, ActorContainer},
+    character::DamageType,
+    message::Message,
+};
+use rg3d::{
+    core::{
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    scene::Scene,
+};
+use std::{path::PathBuf, str::FromStr, sync::mpsc::Sender};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TriggerCondition {
+    /// Actions are performed when an actor enters the volume.
+    Enter,
+    /// Actions are performed when an alive actor leaves the volume.
+    Exit,
+    /// Actions are performed periodically for every actor inside the volume.
+    Stay,
+}
+
+impl TriggerCondition {
+    pub fn id(self) -> u32 {
+        match self {
+            TriggerCondition::Enter => 0,
+            TriggerCondition::Exit => 1,
+            TriggerCondition::Stay => 2,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(TriggerCondition::Enter),
+            1 => Ok(TriggerCondition::Exit),
+            2 => Ok(TriggerCondition::Stay),
+            _ => Err(format!("Invalid trigger condition {}", id)),
+        }
+    }
+}
+
+impl FromStr for TriggerCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "enter" => Ok(TriggerCondition::Enter),
+            "exit" => Ok(TriggerCondition::Exit),
+            "stay" => Ok(TriggerCondition::Stay),
+            _ => Err(format!("unknown trigger condition {}", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ActorFilter {
+    Player,
+    Bots,
+    Any,
+}
+
+impl ActorFilter {
+    pub fn id(self) -> u32 {
+        match self {
+            ActorFilter::Player => 0,
+            ActorFilter::Bots => 1,
+            ActorFilter::Any => 2,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(ActorFilter::Player),
+            1 => Ok(ActorFilter::Bots),
+            2 => Ok(ActorFilter::Any),
+            _ => Err(format!("Invalid actor filter {}", id)),
+        }
+    }
+
+    pub fn accepts(self, actor: &Actor) -> bool {
+        match (self, actor) {
+            (ActorFilter::Any, _)
+            | (ActorFilter::Player, Actor::Player(_))
+            | (ActorFilter::Bots, Actor::Bot(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for ActorFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "player" => Ok(ActorFilter::Player),
+            "bots" => Ok(ActorFilter::Bots),
+            "any" => Ok(ActorFilter::Any),
+            _ => Err(format!("unknown actor filter {}", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TriggerMode {
+    /// Trigger is disabled after first activation.
+    Once,
+    Repeat,
+}
+
+impl TriggerMode {
+    pub fn id(self) -> u32 {
+        match self {
+            TriggerMode::Once => 0,
+            TriggerMode::Repeat => 1,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(TriggerMode::Once),
+            1 => Ok(TriggerMode::Repeat),
+            _ => Err(format!("Invalid trigger mode {}", id)),
+        }
+    }
+}
+
+impl FromStr for TriggerMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "once" => Ok(TriggerMode::Once),
+            "repeat" => Ok(TriggerMode::Repeat),
+            _ => Err(format!("unknown trigger mode {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TriggerAction {
+    /// Spawns bots at every spawn point of a group.
+    SpawnBots {
+        group: String,
+    },
+    PlaySound {
+        path: String,
+    },
+    /// Damages actor that activated the trigger.
+    Damage {
+        amount: f32,
+    },
+    ShowText {
+        text: String,
+    },
+    EndLevel,
+}
+
+impl Default for TriggerAction {
+    fn default() -> Self {
+        TriggerAction::EndLevel
+    }
+}
+
+impl TriggerAction {
+    pub fn id(&self) -> u32 {
+        match self {
+            TriggerAction::SpawnBots { .. } => 0,
+            TriggerAction::PlaySound { .. } => 1,
+            TriggerAction::Damage { .. } => 2,
+            TriggerAction::ShowText { .. } => 3,
+            TriggerAction::EndLevel => 4,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(TriggerAction::SpawnBots {
+                group: Default::default(),
+            }),
+            1 => Ok(TriggerAction::PlaySound {
+                path: Default::default(),
+            }),
+            2 => Ok(TriggerAction::Damage { amount: 0.0 }),
+            3 => Ok(TriggerAction::ShowText {
+                text: Default::default(),
+            }),
+            4 => Ok(TriggerAction::EndLevel),
+            _ => Err(format!("Invalid trigger action {}", id)),
+        }
+    }
+
+    fn make_message(&self, activator: Handle<Actor>, bounds: &AxisAlignedBoundingBox) -> Message {
+        match self {
+            TriggerAction::SpawnBots { group } => Message::SpawnBots {
+                group: group.clone(),
+            },
+            TriggerAction::PlaySound { path } => Message::PlaySound {
+                path: PathBuf::from(path),
+                position: bounds.center(),
+                gain: 1.0,
+                rolloff_factor: 1.0,
+                radius: 5.0,
+            },
+            &TriggerAction::Damage { amount } => Message::DamageActor {
+                actor: activator,
+                who: Default::default(),
+                amount,
+                damage_type: DamageType::Environment,
+                position: None,
+                location: None,
+            },
+            TriggerAction::ShowText { text } => Message::ShowText { text: text.clone() },
+            TriggerAction::EndLevel => Message::EndLevel,
+        }
+    }
+}
+
+impl Visit for TriggerAction {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = Self::from_id(id)?;
+        }
+
+        match self {
+            TriggerAction::SpawnBots { group } => group.visit("Group", visitor)?,
+            TriggerAction::PlaySound { path } => path.visit("Path", visitor)?,
+            TriggerAction::Damage { amount } => amount.visit("Amount", visitor)?,
+            TriggerAction::ShowText { text } => text.visit("Text", visitor)?,
+            TriggerAction::EndLevel => (),
+        }
+
+        visitor.leave_region()
+    }
+}
+
+pub struct Trigger {
+    pub bounds: AxisAlignedBoundingBox,
+    pub condition: TriggerCondition,
+    pub filter: ActorFilter,
+    pub mode: TriggerMode,
+    /// Period in seconds between activations for `Stay` condition, zero means every step.
+    pub interval: f32,
+    pub actions: Vec<TriggerAction>,
+    timer: f32,
+    fired: bool,
+    /// Actors that were inside the volume on previous update.
+    inside: Vec<Handle<Actor>>,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            bounds: Default::default(),
+            condition: TriggerCondition::Enter,
+            filter: ActorFilter::Player,
+            mode: TriggerMode::Once,
+            interval: 0.0,
+            actions: Default::default(),
+            timer: 0.0,
+            fired: false,
+            inside: Default::default(),
+        }
+    }
+}
+
+impl Trigger {
+    /// Creates trigger that kills every actor inside of it.
+    pub fn death_zone() -> Self {
+        Self {
+            condition: TriggerCondition::Stay,
+            filter: ActorFilter::Any,
+            mode: TriggerMode::Repeat,
+            actions: vec![TriggerAction::Damage { amount: 99999.0 }],
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if one-shot trigger was already activated.
+    pub fn is_finished(&self) -> bool {
+        self.mode == TriggerMode::Once && self.fired
+    }
+
+    pub fn update(
+        &mut self,
+        actors: &ActorContainer,
+        scene: &Scene,
+        dt: f32,
+        sender: &Sender<Message>,
+    ) {
+        if self.is_finished() {
+            return;
+        }
+
+        let bounds = &self.bounds;
+        let filter = self.filter;
+        let now_inside = actors
+            .pair_iter()
+            .filter(|(_, actor)| {
+                !actor.is_dead()
+                    && filter.accepts(actor)
+                    && bounds.is_contains_point(actor.position(&scene.physics))
+            })
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+
+        let activators = match self.condition {
+            TriggerCondition::Enter => now_inside
+                .iter()
+                .filter(|handle| !self.inside.contains(handle))
+                .copied()
+                .collect(),
+            // Dead or removed actors are not leaving the volume.
+            TriggerCondition::Exit => self
+                .inside
+                .iter()
+                .filter(|&&handle| {
+                    !now_inside.contains(&handle)
+                        && actors.contains(handle)
+                        && !actors.get(handle).is_dead()
+                })
+                .copied()
+                .collect(),
+            TriggerCondition::Stay => {
+                self.timer -= dt;
+                if self.timer <= 0.0 && !now_inside.is_empty() {
+                    self.timer = self.interval;
+                    now_inside.clone()
+                } else {
+                    Vec::new()
+                }
+            }
+        };
+
+        self.inside = now_inside;
+
+        for activator in activators {
+            for action in self.actions.iter() {
+                sender
+                    .send(action.make_message(activator, &self.bounds))
+                    .unwrap();
+            }
+
+            self.fired = true;
+            if self.mode == TriggerMode::Once {
+                break;
+            }
+        }
+    }
+}
+
+impl Visit for Trigger {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.bounds.visit("Bounds", visitor)?;
+
+        let mut condition = self.condition.id();
+        condition.visit("Condition", visitor)?;
+        let mut filter = self.filter.id();
+        filter.visit("Filter", visitor)?;
+        let mut mode = self.mode.id();
+        mode.visit("Mode", visitor)?;
+        if visitor.is_reading() {
+            self.condition = TriggerCondition::from_id(condition)?;
+            self.filter = ActorFilter::from_id(filter)?;
+            self.mode = TriggerMode::from_id(mode)?;
+        }
+
+        self.interval.visit("Interval", visitor)?;
+        self.actions.visit("Actions", visitor)?;
+        self.timer.visit("Timer", visitor)?;
+        self.fired.visit("Fired", visitor)?;
+        self.inside.visit("Inside", visitor)?;
+
+        visitor.leave_region()
+    }
+}
@@ -0,0 +1,96 @@
+extern crate rg3d;
+
+pub mod actor;
+pub mod bot;
+pub mod character;
+pub mod control_scheme;
+pub mod definition;
+pub mod effects;
+pub mod gui;
+pub mod headless;
+pub mod hitbox;
+pub mod hud;
+pub mod inventory;
+pub mod item;
+pub mod level;
+pub mod menu;
+pub mod message;
+pub mod options_menu;
+pub mod player;
+pub mod replay;
+pub mod results;
+pub mod rng;
+pub mod save;
+pub mod save_browser;
+pub mod weapon;
+
+use rg3d::{
+    animation::{
+        machine::{Machine, PoseNode, State},
+        Animation,
+    },
+    core::pool::Handle,
+    engine::Engine,
+    gui::{
+        message::UiMessage,
+        node::{StubNode, UINode},
+        UserInterface,
+    },
+    resource::model::Model,
+    scene::{node::Node, Scene},
+};
+use std::time::{self, Instant};
+
+pub const FIXED_FPS: f32 = 60.0;
+
+// Define type aliases for engine structs.
+pub type UiNode = UINode<(), StubNode>;
+pub type UINodeHandle = Handle<UiNode>;
+pub type GameEngine = Engine<(), StubNode>;
+pub type Gui = UserInterface<(), StubNode>;
+pub type GuiMessage = UiMessage<(), StubNode>;
+pub type BuildContext<'a> = rg3d::gui::BuildContext<'a, (), StubNode>;
+
+/// Fails if animation resource has no animations.
+pub fn create_play_animation_state(
+    animation_resource: Model,
+    name: &str,
+    machine: &mut Machine,
+    scene: &mut Scene,
+    model: Handle<Node>,
+) -> Result<(Handle<Animation>, Handle<State>), String> {
+    let animation = *animation_resource
+        .retarget_animations(model, scene)
+        .get(0)
+        .ok_or_else(|| format!("Resource of animation {} has no animations!", name))?;
+    let node = machine.add_node(PoseNode::make_play_animation(animation));
+    let state = machine.add_state(State::new(name, node));
+    Ok((animation, state))
+}
+
+#[derive(Copy, Clone)]
+pub struct GameTime {
+    pub clock: time::Instant,
+    pub elapsed: f64,
+    pub delta: f32,
+}
+
+impl GameTime {
+    /// Creates new game time with fixed time step.
+    pub fn new(delta: f32) -> Self {
+        Self {
+            clock: Instant::now(),
+            elapsed: 0.0,
+            delta,
+        }
+    }
+}
+
+// Disable false-positive lint, isize *is* portable.
+#[allow(clippy::enum_clike_unportable_variant)]
+pub enum CollisionGroups {
+    Generic = 1,
+    Projectile = 1 << 1,
+    Actor = 1 << 2,
+    All = std::isize::MAX,
+}
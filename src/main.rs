@@ -1,49 +1,21 @@
-extern crate rg3d;
-
-pub mod actor;
-pub mod bot;
-pub mod character;
-pub mod control_scheme;
-pub mod effects;
-pub mod gui;
-pub mod hud;
-pub mod item;
-pub mod level;
-pub mod menu;
-pub mod message;
-pub mod options_menu;
-pub mod player;
-pub mod weapon;
-
-use crate::{
-    actor::Actor, control_scheme::ControlScheme, hud::Hud, level::Level, menu::Menu,
-    message::Message,
-};
 use rg3d::{
-    animation::{
-        machine::{Machine, PoseNode, State},
-        Animation,
-    },
     core::{
         color::Color,
         pool::Handle,
         visitor::{Visit, VisitResult, Visitor},
     },
-    engine::Engine,
     event::{ElementState, Event, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     gui::{
         grid::{Column, GridBuilder, Row},
-        message::{MessageDirection, ProgressBarMessage, TextMessage, UiMessage, WidgetMessage},
-        node::{StubNode, UINode},
+        message::{MessageDirection, ProgressBarMessage, TextMessage, WidgetMessage},
         progress_bar::ProgressBarBuilder,
         text::TextBuilder,
         widget::WidgetBuilder,
-        HorizontalAlignment, UserInterface, VerticalAlignment,
+        HorizontalAlignment, VerticalAlignment,
     },
     renderer::ShadowMapPrecision,
-    resource::model::Model,
-    scene::{node::Node, Scene},
+    scene::Scene,
     sound::{
         context::Context,
         source::{generic::GenericSourceBuilder, SoundSource, Status},
@@ -53,47 +25,41 @@ use rg3d::{
         translate_event,
     },
 };
+use station_iapetus::{
+    actor::Actor,
+    bot::BotDefinition,
+    control_scheme::ControlScheme,
+    definition::{self, DefinitionWatcher},
+    hud::Hud,
+    inventory::AmmoDefinition,
+    item::ItemDefinition,
+    level::{manifest::LevelManifest, stats::Outcome, Level},
+    menu::Menu,
+    message::Message,
+    player::PlayerDefinition,
+    replay::Replay,
+    results::ResultsScreen,
+    rng::GameRng,
+    save::{self, ReadVersionScope, SaveHeader, SaveSlot},
+    weapon::{projectile::ProjectileDefinition, WeaponDefinition},
+    BuildContext, GameEngine, GameTime, UINodeHandle, UiNode, FIXED_FPS,
+};
 use std::{
     fs::File,
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{
         mpsc::{self, Receiver, Sender},
         Arc, Mutex, RwLock,
     },
     thread,
-    time::{self, Duration, Instant},
+    time::{self, Duration},
 };
 
-const FIXED_FPS: f32 = 60.0;
-
-// Define type aliases for engine structs.
-pub type UiNode = UINode<(), StubNode>;
-pub type UINodeHandle = Handle<UiNode>;
-pub type GameEngine = Engine<(), StubNode>;
-pub type Gui = UserInterface<(), StubNode>;
-pub type GuiMessage = UiMessage<(), StubNode>;
-pub type BuildContext<'a> = rg3d::gui::BuildContext<'a, (), StubNode>;
-
-pub fn create_play_animation_state(
-    animation_resource: Model,
-    name: &str,
-    machine: &mut Machine,
-    scene: &mut Scene,
-    model: Handle<Node>,
-) -> (Handle<Animation>, Handle<State>) {
-    let animation = *animation_resource
-        .retarget_animations(model, scene)
-        .get(0)
-        .unwrap();
-    let node = machine.add_node(PoseNode::make_play_animation(animation));
-    let state = machine.add_state(State::new(name, node));
-    (animation, state)
-}
-
 pub struct Game {
     menu: Menu,
     hud: Hud,
+    results: ResultsScreen,
     engine: GameEngine,
     level: Option<Level>,
     debug_text: UINodeHandle,
@@ -108,6 +74,42 @@ pub struct Game {
     loading_screen: LoadingScreen,
     menu_sound_context: Context,
     music: Handle<SoundSource>,
+    level_manifest: LevelManifest,
+    definition_watcher: DefinitionWatcher,
+    /// Path to a file to which input of every level will be recorded.
+    record_path: Option<PathBuf>,
+    /// Amount of levels recorded in this session.
+    recorded_levels: u32,
+    /// Replay that will be played back as soon as its level is loaded.
+    pending_replay: Option<Replay>,
+}
+
+/// Options passed to the game from command line.
+#[derive(Default)]
+pub struct LaunchOptions {
+    /// `--record <path>` - records input of the session to given file, second and further
+    /// levels of the session are recorded to numbered files next to it.
+    record: Option<PathBuf>,
+    /// `--replay <path>` - plays back previously recorded session.
+    replay: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    fn from_args() -> Self {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => options.record = args.next().map(PathBuf::from),
+                "--replay" => options.replay = args.next().map(PathBuf::from),
+                _ => Log::writeln(
+                    MessageKind::Warning,
+                    format!("Unknown command line argument {}", arg),
+                ),
+            }
+        }
+        options
+    }
 }
 
 struct LoadingScreen {
@@ -159,28 +161,12 @@ impl LoadingScreen {
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct GameTime {
-    clock: time::Instant,
-    elapsed: f64,
-    delta: f32,
-}
-
-// Disable false-positive lint, isize *is* portable.
-#[allow(clippy::enum_clike_unportable_variant)]
-pub enum CollisionGroups {
-    Generic = 1,
-    Projectile = 1 << 1,
-    Actor = 1 << 2,
-    All = std::isize::MAX,
-}
-
 pub struct LoadContext {
     level: Option<(Level, Scene)>,
 }
 
 impl Game {
-    pub fn run() {
+    pub fn run(options: LaunchOptions) {
         let events_loop = EventLoop::<()>::new();
 
         let primary_monitor = events_loop.primary_monitor().unwrap();
@@ -196,6 +182,16 @@ impl Game {
 
         let mut engine = GameEngine::new(window_builder, &events_loop, false).unwrap();
 
+        // Definitions are checked against assets, so they're loaded by resource manager of the
+        // engine which keeps them cached for the game.
+        if let Err(e) = definition::load_all(&engine.resource_manager) {
+            Log::writeln(
+                MessageKind::Error,
+                format!("Unable to load game definitions. Reason: {}", e),
+            );
+            std::process::exit(1);
+        }
+
         let mut settings = engine.renderer.get_quality_settings();
         settings.point_shadow_map_precision = ShadowMapPrecision::Full;
         settings.spot_shadow_map_precision = ShadowMapPrecision::Full;
@@ -205,13 +201,18 @@ impl Game {
 
         let control_scheme = Arc::new(RwLock::new(ControlScheme::default()));
 
+        let level_manifest = LevelManifest::load(LevelManifest::PATH).unwrap();
+        let definition_watcher = DefinitionWatcher::new()
+            .watch::<WeaponDefinition>()
+            .watch::<BotDefinition>()
+            .watch::<ItemDefinition>()
+            .watch::<AmmoDefinition>()
+            .watch::<ProjectileDefinition>()
+            .watch::<PlayerDefinition>();
+
         let fixed_timestep = 1.0 / FIXED_FPS;
 
-        let time = GameTime {
-            clock: Instant::now(),
-            elapsed: 0.0,
-            delta: fixed_timestep,
-        };
+        let time = GameTime::new(fixed_timestep);
 
         let (tx, rx) = mpsc::channel();
 
@@ -249,6 +250,7 @@ impl Game {
             hud: Hud::new(&mut engine),
             running: true,
             menu: Menu::new(&mut engine, control_scheme.clone(), tx.clone()),
+            results: ResultsScreen::new(&mut engine, tx.clone()),
             control_scheme,
             debug_text: Handle::NONE,
             engine,
@@ -259,10 +261,22 @@ impl Game {
             events_receiver: rx,
             events_sender: tx,
             load_context: None,
+            level_manifest,
+            definition_watcher,
+            record_path: options.record,
+            recorded_levels: 0,
+            pending_replay: None,
         };
 
         game.create_debug_ui();
 
+        if let Some(path) = options.replay {
+            match Replay::load(path) {
+                Ok(replay) => game.play_replay(replay),
+                Err(e) => Log::writeln(MessageKind::Error, e),
+            }
+        }
+
         events_loop.run(move |event, _, control_flow| {
             game.process_input_event(&event);
 
@@ -281,6 +295,7 @@ impl Game {
                                 game.level.as_ref(),
                                 &ui_event,
                             );
+                            game.results.handle_ui_event(&mut game.engine, &ui_event);
                         }
                     }
                     if !game.running {
@@ -330,31 +345,44 @@ impl Game {
             .build(&mut self.engine.user_interface.build_ctx());
     }
 
-    pub fn save_game(&mut self) -> VisitResult {
+    pub fn save_game(&mut self, slot: &str) -> VisitResult {
+        let metadata = match self.level.as_ref() {
+            Some(level) => level.save_metadata(),
+            None => return Err("There is no level to save!".to_owned().into()),
+        };
+
+        SaveSlot::prepare_dir()?;
+
         let mut visitor = Visitor::new();
 
-        // Visit engine state first.
-        self.engine.visit("GameEngine", &mut visitor)?;
-        self.level.visit("Level", &mut visitor)?;
-        self.menu_sound_context
-            .visit("MenuSoundContext", &mut visitor)?;
-        self.music.visit("Music", &mut visitor)?;
+        SaveHeader::current().visit("Header", &mut visitor)?;
+
+        self.visit_game_state(&mut visitor)?;
 
         // Debug output
-        if let Ok(mut file) = File::create(Path::new("save.txt")) {
+        if let Ok(mut file) = File::create(SaveSlot::debug_path(slot)) {
             file.write_all(visitor.save_text().as_bytes()).unwrap();
         }
 
-        visitor.save_binary(Path::new("save.bin"))
+        visitor.save_binary(&SaveSlot::data_path(slot))?;
+
+        SaveSlot::write_metadata(slot, &metadata)?;
+
+        Ok(())
     }
 
-    pub fn load_game(&mut self) -> VisitResult {
+    pub fn load_game(&mut self, slot: &str) -> VisitResult {
         Log::writeln(
             MessageKind::Information,
-            "Attempting load a save...".to_owned(),
+            format!("Attempting load a save {}...", slot),
         );
 
-        let mut visitor = Visitor::load_binary(Path::new("save.bin"))?;
+        let mut visitor = Visitor::load_binary(&SaveSlot::data_path(slot))?;
+
+        // Check version before destroying current level, so incompatible save won't leave
+        // the game in broken state.
+        let header = SaveHeader::read(&mut visitor);
+        header.check_compatibility()?;
 
         // Clean up.
         self.destroy_level();
@@ -362,13 +390,26 @@ impl Game {
         // Load engine state first
         Log::writeln(
             MessageKind::Information,
-            "Trying to load a save file...".to_owned(),
+            format!(
+                "Trying to load a save file of version {}...",
+                header.version
+            ),
         );
-        self.engine.visit("GameEngine", &mut visitor)?;
-        self.level.visit("Level", &mut visitor)?;
-        self.menu_sound_context
-            .visit("MenuSoundContext", &mut visitor)?;
-        self.music.visit("Music", &mut visitor)?;
+        {
+            let _version_scope = ReadVersionScope::new(header.version);
+            if let Err(e) = self.visit_game_state(&mut visitor) {
+                // Current level is already destroyed, so partially loaded state is dropped
+                // and the game returns to the main menu.
+                self.level = None;
+                self.engine.scenes.clear();
+                self.set_menu_visible(true);
+                return Err(e);
+            }
+        }
+
+        if let Some(level) = self.level.as_mut() {
+            save::migrate(level, &self.level_manifest, header.version);
+        }
 
         Log::writeln(
             MessageKind::Information,
@@ -393,8 +434,29 @@ impl Game {
         Ok(())
     }
 
+    /// Visits state of the game, engine state goes first.
+    fn visit_game_state(&mut self, visitor: &mut Visitor) -> VisitResult {
+        self.engine.visit("GameEngine", visitor)?;
+        self.level.visit("Level", visitor)?;
+        self.menu_sound_context.visit("MenuSoundContext", visitor)?;
+        self.music.visit("Music", visitor)
+    }
+
     fn destroy_level(&mut self) {
         if let Some(ref mut level) = self.level.take() {
+            if let Some(replay) = level.stop_recording() {
+                if let Some(path) = self.record_path.as_ref() {
+                    let path = numbered_path(path, self.recorded_levels);
+                    self.recorded_levels += 1;
+                    match replay.save(&path) {
+                        Ok(_) => Log::writeln(
+                            MessageKind::Information,
+                            format!("Replay saved to {}", path.display()),
+                        ),
+                        Err(e) => Log::writeln(MessageKind::Error, e),
+                    }
+                }
+            }
             level.destroy(&mut self.engine);
             Log::writeln(
                 MessageKind::Information,
@@ -403,7 +465,50 @@ impl Game {
         }
     }
 
+    /// Shows results of current level and destroys the level.
+    fn end_match(&mut self, outcome: Outcome) {
+        let ui = &mut self.engine.user_interface;
+        if let Some(level) = self.level.as_mut() {
+            // Seed is taken before the level is destroyed, so the session stays deterministic.
+            let seed = level.next_level_seed();
+            self.results.open(
+                ui,
+                outcome,
+                level.time(),
+                level.stats(),
+                level.id.clone(),
+                level.next_level().map(ToOwned::to_owned),
+                seed,
+            );
+        }
+        self.hud.set_visible(ui, false);
+        self.destroy_level();
+    }
+
     pub fn start_new_game(&mut self) {
+        let start = self.level_manifest.start.clone();
+        self.load_level(&start, GameRng::time_seed());
+    }
+
+    /// Loads level of the replay with the same seed and plays the replay back.
+    pub fn play_replay(&mut self, replay: Replay) {
+        let (level, seed) = (replay.level.clone(), replay.seed);
+        self.pending_replay = Some(replay);
+        self.load_level(&level, seed);
+    }
+
+    pub fn load_level(&mut self, id: &str, seed: u64) {
+        let definition = match self.level_manifest.definition(id) {
+            Some(definition) => definition.clone(),
+            None => {
+                Log::writeln(
+                    MessageKind::Error,
+                    format!("Unable to load level {}: no such level in manifest!", id),
+                );
+                return;
+            }
+        };
+
         self.destroy_level();
 
         let ctx = Arc::new(Mutex::new(LoadContext { level: None }));
@@ -424,8 +529,16 @@ impl Game {
         let control_scheme = self.control_scheme.clone();
         let sender = self.events_sender.clone();
 
+        // Log the seed, it is required to reproduce the session.
+        Log::writeln(
+            MessageKind::Information,
+            format!("Loading level {} with seed {}", definition.id, seed),
+        );
+
         std::thread::spawn(move || {
             let level = rg3d::futures::executor::block_on(Level::new(
+                definition,
+                seed,
                 resource_manager,
                 control_scheme,
                 sender,
@@ -446,14 +559,21 @@ impl Game {
     }
 
     pub fn update(&mut self, time: GameTime) {
+        let cursor_needed =
+            self.is_menu_visible() || self.results.is_open(&self.engine.user_interface);
         let window = self.engine.get_window();
-        window.set_cursor_visible(self.is_menu_visible());
-        let _ = window.set_cursor_grab(!self.is_menu_visible());
+        window.set_cursor_visible(cursor_needed);
+        let _ = window.set_cursor_grab(!cursor_needed);
 
         if let Some(ctx) = self.load_context.clone() {
             if let Ok(mut ctx) = ctx.try_lock() {
                 if let Some((mut level, scene)) = ctx.level.take() {
                     level.scene = self.engine.scenes.add(scene);
+                    if let Some(replay) = self.pending_replay.take() {
+                        level.start_playback(replay);
+                    } else if self.record_path.is_some() {
+                        level.start_recording();
+                    }
                     self.level = Some(level);
                     self.load_context = None;
                     self.set_menu_visible(false);
@@ -478,8 +598,17 @@ impl Game {
 
         self.engine.update(time.delta);
 
+        if self
+            .definition_watcher
+            .update(time.delta, &self.engine.resource_manager)
+        {
+            if let Some(level) = self.level.as_mut() {
+                level.refresh_definitions(&mut self.engine.scenes[level.scene]);
+            }
+        }
+
         if let Some(ref mut level) = self.level {
-            level.update(&mut self.engine, time);
+            level.update(&mut self.engine.scenes[level.scene], time);
             let ui = &mut self.engine.user_interface;
             let player = level.get_player();
             if player.is_some() {
@@ -489,47 +618,91 @@ impl Game {
                 self.hud.set_armor(ui, player.get_armor());
                 let current_weapon = player.current_weapon();
                 if current_weapon.is_some() {
-                    self.hud
-                        .set_ammo(ui, level.weapons()[current_weapon].ammo());
+                    let weapon = &level.weapons()[current_weapon];
+                    let reserve = player.inventory.ammo(weapon.ammo_kind());
+                    self.hud.set_ammo(ui, weapon, reserve);
+                    let scene = &self.engine.scenes[level.scene];
+                    let spread = weapon.spread() * player.spread_factor(&scene.physics);
+                    self.hud.set_spread(ui, spread);
                 }
+                self.hud.set_inventory(ui, &player.inventory);
                 self.hud.set_is_died(ui, false);
             } else {
                 self.hud.set_is_died(ui, true);
             }
+            let objectives = level
+                .objectives()
+                .visible()
+                .map(|o| {
+                    let mark = if o.is_completed() { "[x]" } else { "[ ]" };
+                    format!("{} {}", mark, o.status())
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            self.hud.set_objectives(ui, objectives);
+            self.hud.update(ui, time.delta);
         }
 
-        self.handle_messages(time);
+        self.handle_messages();
     }
 
-    fn handle_messages(&mut self, time: GameTime) {
+    fn handle_messages(&mut self) {
         while let Ok(message) = self.events_receiver.try_recv() {
             match &message {
                 Message::StartNewGame => {
                     self.start_new_game();
                 }
-                Message::SaveGame => match self.save_game() {
-                    Ok(_) => {
-                        Log::writeln(MessageKind::Information, "Successfully saved".to_owned())
-                    }
+                Message::LoadLevel { id, seed } => {
+                    self.load_level(id, *seed);
+                }
+                Message::SaveGame { slot } => match self.save_game(slot) {
+                    Ok(_) => Log::writeln(
+                        MessageKind::Information,
+                        format!("Successfully saved to slot {}", slot),
+                    ),
                     Err(e) => Log::writeln(
                         MessageKind::Error,
                         format!("Failed to make a save, reason: {}", e),
                     ),
                 },
-                Message::LoadGame => {
-                    if let Err(e) = self.load_game() {
+                Message::Autosave => {
+                    let slot = SaveSlot::next_autosave_name();
+                    match self.save_game(&slot) {
+                        Ok(_) => Log::writeln(
+                            MessageKind::Information,
+                            format!("Autosaved to slot {}", slot),
+                        ),
+                        Err(e) => Log::writeln(
+                            MessageKind::Error,
+                            format!("Failed to autosave, reason: {}", e),
+                        ),
+                    }
+                }
+                Message::LoadGame { slot } => {
+                    if let Err(e) = self.load_game(slot) {
                         Log::writeln(
                             MessageKind::Error,
                             format!("Failed to load saved game. Reason: {:?}", e),
                         );
+                        self.menu.show_message(
+                            &mut self.engine.user_interface,
+                            "Save is incompatible",
+                            format!("Unable to load save \"{}\".\n{:?}", slot, e),
+                        );
                     }
                 }
                 Message::QuitGame => {
                     self.destroy_level();
                     self.running = false;
                 }
-                Message::EndMatch => {
+                &Message::EndMatch { outcome } => self.end_match(outcome),
+                Message::ShowMainMenu => {
                     self.destroy_level();
+                    self.set_menu_visible(true);
+                }
+                Message::ShowText { text } => {
+                    self.hud
+                        .show_message(&mut self.engine.user_interface, text.clone());
                 }
                 Message::SetMusicVolume { volume } => {
                     self.menu_sound_context
@@ -542,9 +715,9 @@ impl Game {
 
             if let Some(ref mut level) = self.level {
                 rg3d::futures::executor::block_on(level.handle_message(
-                    &mut self.engine,
+                    &mut self.engine.scenes[level.scene],
+                    self.engine.resource_manager.clone(),
                     &message,
-                    time,
                 ));
             }
         }
@@ -632,6 +805,21 @@ impl Game {
     }
 }
 
+/// Returns path with given number appended to the file name, zero leaves the path as is.
+fn numbered_path(path: &Path, number: u32) -> PathBuf {
+    if number == 0 {
+        return path.to_owned();
+    }
+    let stem = path
+        .file_stem()
+        .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+    let name = match path.extension() {
+        Some(extension) => format!("{}_{}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}_{}", stem, number),
+    };
+    path.with_file_name(name)
+}
+
 fn main() {
-    Game::run();
+    Game::run(LaunchOptions::from_args());
 }
@@ -1,5 +1,9 @@
 use crate::{
-    control_scheme::ControlScheme, level::Level, message::Message, options_menu::OptionsMenu,
+    control_scheme::ControlScheme,
+    level::Level,
+    message::Message,
+    options_menu::OptionsMenu,
+    save_browser::{SaveBrowser, SaveBrowserMode},
     GameEngine, Gui, GuiMessage, UiNode,
 };
 use rg3d::{
@@ -8,7 +12,11 @@ use rg3d::{
     gui::{
         button::ButtonBuilder,
         grid::{Column, GridBuilder, Row},
-        message::{ButtonMessage, MessageDirection, UiMessageData, WidgetMessage, WindowMessage},
+        message::{
+            ButtonMessage, MessageBoxMessage, MessageDirection, UiMessageData, WidgetMessage,
+            WindowMessage,
+        },
+        messagebox::{MessageBoxBuilder, MessageBoxButtons},
         ttf::{Font, SharedFont},
         widget::WidgetBuilder,
         window::{WindowBuilder, WindowTitle},
@@ -29,6 +37,8 @@ pub struct Menu {
     btn_load_game: Handle<UiNode>,
     btn_quit_game: Handle<UiNode>,
     options_menu: OptionsMenu,
+    save_browser: SaveBrowser,
+    message_box: Handle<UiNode>,
 }
 
 impl Menu {
@@ -148,6 +158,13 @@ impl Menu {
         .add_column(Column::stretch())
         .build(ctx);
 
+        let message_box = MessageBoxBuilder::new(
+            WindowBuilder::new(WidgetBuilder::new().with_width(400.0).with_height(160.0))
+                .open(false),
+        )
+        .with_buttons(MessageBoxButtons::Ok)
+        .build(ctx);
+
         Self {
             sender: sender.clone(),
             root,
@@ -157,9 +174,21 @@ impl Menu {
             btn_load_game,
             btn_quit_game,
             options_menu: OptionsMenu::new(engine, control_scheme, sender.clone()),
+            save_browser: SaveBrowser::new(engine, sender),
+            message_box,
         }
     }
 
+    /// Shows a message box with given title and text on top of the menu.
+    pub fn show_message(&self, ui: &mut Gui, title: &str, text: String) {
+        ui.send_message(MessageBoxMessage::open(
+            self.message_box,
+            MessageDirection::ToWidget,
+            Some(title.to_owned()),
+            Some(text),
+        ));
+    }
+
     pub fn set_visible(&mut self, ui: &mut Gui, visible: bool) {
         ui.send_message(WidgetMessage::visibility(
             self.root,
@@ -171,6 +200,7 @@ impl Menu {
                 self.options_menu.window,
                 MessageDirection::ToWidget,
             ));
+            self.save_browser.close(ui);
         }
     }
 
@@ -208,9 +238,14 @@ impl Menu {
                 if message.destination() == self.btn_new_game {
                     self.sender.send(Message::StartNewGame).unwrap();
                 } else if message.destination() == self.btn_save_game {
-                    self.sender.send(Message::SaveGame).unwrap();
+                    // There is nothing to save without a level.
+                    if level.is_some() {
+                        self.save_browser
+                            .open(&mut engine.user_interface, SaveBrowserMode::Save);
+                    }
                 } else if message.destination() == self.btn_load_game {
-                    self.sender.send(Message::LoadGame).unwrap();
+                    self.save_browser
+                        .open(&mut engine.user_interface, SaveBrowserMode::Load);
                 } else if message.destination() == self.btn_quit_game {
                     self.sender.send(Message::QuitGame).unwrap();
                 } else if message.destination() == self.btn_settings {
@@ -224,5 +259,6 @@ impl Menu {
         }
 
         self.options_menu.handle_ui_event(engine, level, message);
+        self.save_browser.handle_ui_event(engine, message);
     }
 }
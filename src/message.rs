@@ -12,8 +12,11 @@
 use crate::{
     actor::Actor,
     bot::BotKind,
+    character::DamageType,
     effects::EffectKind,
+    hitbox::HitLocation,
     item::{Item, ItemKind},
+    level::stats::Outcome,
     weapon::projectile::ProjectileKind,
     weapon::{Weapon, WeaponKind},
 };
@@ -39,14 +42,18 @@ pub enum Message {
     SpawnBot {
         spawn_point_id: usize,
     },
+    /// Spawns bots at every not yet used spawn point of a group.
+    SpawnBots {
+        group: String,
+    },
     /// Gives item of specified kind to a given actor. Basically it means that actor will take
-    /// item and consume it immediately (heal itself, add ammo, etc.)
+    /// item and put it into inventory (add medkit, ammo to reserve, etc.) if there is room.
     GiveItem {
         actor: Handle<Actor>,
         kind: ItemKind,
     },
-    /// Gives specified actor to a given actor. Removes item from level if temporary or deactivates
-    /// it for short period of time if it constant.
+    /// Gives specified item to a given actor. Removes item from level if temporary or deactivates
+    /// it until respawn time of the item passes if it constant. Inactive items are ignored.
     PickUpItem {
         actor: Handle<Actor>,
         item: Handle<Item>,
@@ -62,11 +69,18 @@ pub enum Message {
         direction: Vector3<f32>,
         initial_velocity: Vector3<f32>,
         owner: Handle<Weapon>,
+        /// Actor who fired the projectile, damage and kills are attributed to the actor. Can be
+        /// Handle::NONE.
+        who: Handle<Actor>,
     },
     ShootWeapon {
         weapon: Handle<Weapon>,
         direction: Option<Vector3<f32>>,
     },
+    /// Refills magazine of a weapon from reserve of its owner.
+    ReloadWeapon {
+        weapon: Handle<Weapon>,
+    },
     ShootRay {
         weapon: Handle<Weapon>,
         begin: Vector3<f32>,
@@ -91,20 +105,69 @@ pub enum Message {
         /// or not from any actor.
         who: Handle<Actor>,
         amount: f32,
+        damage_type: DamageType,
+        /// Point where the actor was hit, effects of hit are created there. Center of the actor
+        /// is used if damage has no exact point, like explosions or falls.
+        position: Option<Vector3<f32>>,
+        /// Hitbox of the actor that was hit, None if the hit had no exact location.
+        location: Option<HitLocation>,
+    },
+    /// Shot of an actor has hit another actor. It is sent once per shot no matter how many
+    /// actors the shot has damaged, splash damage of explosions is not a hit.
+    ShotHit {
+        who: Handle<Actor>,
+        location: Option<HitLocation>,
+    },
+    /// Damages actors around given position, pushes rigid bodies and shakes camera.
+    CreateExplosion {
+        position: Vector3<f32>,
+        /// Normal of surface at the center of explosion.
+        normal: Vector3<f32>,
+        radius: f32,
+        damage: f32,
+        /// Actor who caused the explosion, can be Handle::NONE.
+        who: Handle<Actor>,
     },
     CreateEffect {
         kind: EffectKind,
         position: Vector3<f32>,
         orientation: UnitQuaternion<f32>,
     },
-    /// Save game state to a file. TODO: Add filename field.
-    SaveGame,
-    /// Loads game state from a file. TODO: Add filename field.
-    LoadGame,
+    /// Saves game state to a save slot with given name, overwrites existing slot.
+    SaveGame {
+        slot: String,
+    },
+    /// Loads game state from a save slot with given name.
+    LoadGame {
+        slot: String,
+    },
     StartNewGame,
+    /// Destroys current level (if any) and loads a level with given id from level manifest
+    /// with given seed.
+    LoadLevel {
+        id: String,
+        seed: u64,
+    },
     QuitGame,
     SetMusicVolume {
         volume: f32,
     },
-    EndMatch,
+    /// Ends current level, game shows results screen with statistics of the level.
+    EndMatch {
+        outcome: Outcome,
+    },
+    /// Destroys current level (if any) and opens main menu.
+    ShowMainMenu,
+    /// Saves the game to the oldest of rotating autosave slots.
+    Autosave,
+    /// Respawns dead player at last reached checkpoint.
+    RespawnPlayer,
+    /// Shows a text message on the HUD for a few seconds.
+    ShowText {
+        text: String,
+    },
+    /// Ends current level with victory.
+    EndLevel,
+    /// Sent when every objective of current level is completed, it ends the level.
+    ObjectivesCompleted,
 }
@@ -78,7 +78,7 @@ impl LowerBodyMachine {
         scene: &mut Scene,
         model: Handle<Node>,
         resource_manager: ResourceManager,
-    ) -> Self {
+    ) -> Result<Self, String> {
         let mut machine = Machine::new();
 
         // Load animations in parallel.
@@ -104,7 +104,7 @@ impl LowerBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (jump_animation, jump_state) = create_play_animation_state(
             jump_animation_resource.unwrap(),
@@ -112,7 +112,7 @@ impl LowerBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (_, fall_state) = create_play_animation_state(
             falling_animation_resource.unwrap(),
@@ -120,7 +120,7 @@ impl LowerBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (land_animation, land_state) = create_play_animation_state(
             landing_animation_resource.unwrap(),
@@ -128,7 +128,7 @@ impl LowerBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let WalkStateDefinition {
             walk_animation,
@@ -228,7 +228,7 @@ impl LowerBodyMachine {
             Self::IDLE_TO_FALL,
         ));
 
-        Self {
+        Ok(Self {
             machine,
             jump_animation,
             walk_animation,
@@ -240,7 +240,7 @@ impl LowerBodyMachine {
             fall_state,
             land_state,
             run_animation,
-        }
+        })
     }
 
     pub fn apply(&mut self, scene: &mut Scene, dt: f32, input: LowerBodyMachineInput) {
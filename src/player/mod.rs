@@ -1,6 +1,9 @@
 use crate::{
-    character::Character,
+    actor::Actor,
+    character::{Character, DamageType, Resistances},
     control_scheme::{ControlButton, ControlScheme},
+    definition::{self, Definition, Storage},
+    hitbox::{self, HitboxDefinition},
     level::UpdateContext,
     message::Message,
     player::{
@@ -34,6 +37,7 @@ use rg3d::{
         ColliderHandle, Scene,
     },
 };
+use serde::{Deserialize, Serialize};
 use std::{
     ops::{Deref, DerefMut},
     sync::{mpsc::Sender, Arc, RwLock},
@@ -42,6 +46,62 @@ use std::{
 mod lower_body;
 mod upper_body;
 
+/// Max offset of camera when it shakes.
+const CAMERA_SHAKE_AMPLITUDE: f32 = 0.1;
+
+/// Time in seconds for which camera shake of full strength fades out.
+const CAMERA_SHAKE_DURATION: f32 = 0.6;
+
+/// Settings of the player, they're loaded from `data/player`, which must have exactly one
+/// definition with id 0.
+#[derive(Deserialize, Default, Debug)]
+pub struct PlayerDefinition {
+    pub id: u32,
+    pub name: String,
+    /// Multipliers of damage the player takes.
+    #[serde(default)]
+    pub resistances: Resistances,
+    /// Player without hitboxes is hit by its body.
+    #[serde(default)]
+    pub hitboxes: Vec<HitboxDefinition>,
+    /// Player takes no damage from falls shorter than this time in seconds.
+    #[serde(default)]
+    pub safe_fall_time: f32,
+    /// Damage of fall per second of falling beyond safe fall time, falls are harmless if it
+    /// is not set.
+    #[serde(default)]
+    pub fall_damage_per_second: f32,
+}
+
+static DEFINITIONS: Storage<PlayerDefinition> = RwLock::new(None);
+
+impl Definition for PlayerDefinition {
+    const PATH: &'static str = "data/player";
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn storage() -> &'static Storage<Self> {
+        &DEFINITIONS
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.id != 0 {
+            return Err("id of the player definition must be 0".to_owned());
+        }
+        if self.safe_fall_time < 0.0 || self.fall_damage_per_second < 0.0 {
+            return Err("fall damage settings of the player must be non-negative".to_owned());
+        }
+        self.resistances.validate()?;
+        hitbox::validate(&self.hitboxes)
+    }
+}
+
 /// Creates a camera at given position with a skybox.
 pub async fn create_camera(
     resource_manager: ResourceManager,
@@ -132,7 +192,9 @@ pub fn make_walk_state(
     }
 }
 
-#[derive(Default)]
+/// State of player's input. Every change of the state is a separate input "frame", which can
+/// be recorded and then fed back into the player to reproduce a session.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct InputController {
     walk_forward: bool,
     walk_backward: bool,
@@ -145,6 +207,16 @@ pub struct InputController {
     toss_grenade: bool,
     shoot: bool,
     run: bool,
+    // Weapon change is an impulse rather than a state, these flags are reset right after
+    // input is applied.
+    next_weapon: bool,
+    prev_weapon: bool,
+    #[serde(default)]
+    interact: bool,
+    #[serde(default)]
+    use_medkit: bool,
+    #[serde(default)]
+    reload: bool,
 }
 
 impl Deref for Player {
@@ -214,6 +286,11 @@ pub struct Player {
     in_air_time: f32,
     velocity: Vector3<f32>, // Horizontal velocity, Y is ignored.
     target_velocity: Vector3<f32>,
+    /// Set when Use button was pressed, level takes the request on next update.
+    use_requested: bool,
+    /// Strength of camera shake in [0; 1] range, it fades out over time.
+    camera_shake: f32,
+    definition: Arc<PlayerDefinition>,
 }
 
 impl Visit for Player {
@@ -244,6 +321,9 @@ impl Visit for Player {
         self.run_factor.visit("RunFactor", visitor)?;
         self.target_run_factor.visit("TargetRunFactor", visitor)?;
         self.in_air_time.visit("InAirTime", visitor)?;
+        if visitor.is_reading() {
+            self.definition = Self::get_definition();
+        }
         self.velocity.visit("Velocity", visitor)?;
         self.target_velocity.visit("TargetVelocity", visitor)?;
 
@@ -258,6 +338,13 @@ impl Visit for Player {
 }
 
 impl Player {
+    pub fn get_definition() -> Arc<PlayerDefinition> {
+        definition::registry::<PlayerDefinition>()
+            .get(0)
+            .cloned()
+            .expect("player definition is not loaded")
+    }
+
     pub async fn new(
         scene: &mut Scene,
         resource_manager: ResourceManager,
@@ -324,10 +411,13 @@ impl Player {
         scene.physics_binder.bind(pivot, body.into());
 
         let locomotion_machine =
-            LowerBodyMachine::new(scene, model_handle, resource_manager.clone()).await;
+            LowerBodyMachine::new(scene, model_handle, resource_manager.clone())
+                .await
+                .unwrap();
 
-        let combat_machine =
-            UpperBodyMachine::new(scene, model_handle, resource_manager.clone()).await;
+        let combat_machine = UpperBodyMachine::new(scene, model_handle, resource_manager.clone())
+            .await
+            .unwrap();
 
         scene.graph.update_hierarchical_data();
 
@@ -363,11 +453,15 @@ impl Player {
 
         scene.graph.link_nodes(weapon_origin, hand);
 
+        let definition = Self::get_definition();
+        let hitboxes = hitbox::create_hitboxes(&definition.hitboxes, model_handle, scene);
+
         Self {
             character: Character {
                 pivot,
                 body: body.into(),
                 weapon_pivot,
+                hitboxes,
                 sender: Some(sender),
                 ..Default::default()
             },
@@ -412,6 +506,9 @@ impl Player {
             run_factor: 0.0,
             target_run_factor: 0.0,
             target_velocity: Default::default(),
+            use_requested: false,
+            camera_shake: 0.0,
+            definition,
         }
     }
 
@@ -423,12 +520,51 @@ impl Player {
         self.camera
     }
 
+    pub fn is_aiming(&self) -> bool {
+        self.controller.aim
+    }
+
+    /// Kicks view up and aside by given angles in degrees.
+    pub fn apply_recoil(&mut self, pitch: f32, yaw: f32) {
+        self.controller.pitch = (self.controller.pitch - pitch.to_radians())
+            .max(-90.0f32.to_radians())
+            .min(90.0f32.to_radians());
+        self.controller.yaw += yaw.to_radians();
+    }
+
+    /// Shakes camera with given strength, stronger shake overrides weaker one.
+    pub fn shake_camera(&mut self, strength: f32) {
+        self.camera_shake = self.camera_shake.max(strength.min(1.0));
+    }
+
+    pub fn resistances(&self) -> Resistances {
+        self.definition.resistances
+    }
+
+    /// Picks up reloaded definition, shapes of hitboxes stay the same.
+    pub fn refresh_definition(&mut self) {
+        self.definition = Self::get_definition();
+        hitbox::refresh_hitboxes(&mut self.character.hitboxes, &self.definition.hitboxes);
+    }
+
     pub fn can_be_removed(&self, _scene: &Scene) -> bool {
         self.health <= 0.0
     }
 
-    pub fn update(&mut self, context: &mut UpdateContext) {
-        let UpdateContext { time, scene, .. } = context;
+    pub fn clean_up(&mut self, scene: &mut Scene) {
+        // Camera is not attached to character's pivot, so it must be removed separately.
+        scene.remove_node(self.camera_pivot);
+        self.character.clean_up(scene);
+    }
+
+    pub fn update(&mut self, self_handle: Handle<Actor>, context: &mut UpdateContext) {
+        let UpdateContext {
+            time,
+            level_time,
+            scene,
+            weapons,
+            ..
+        } = context;
 
         let mut sound_context = scene.sound_context.state();
         let listener = sound_context.listener_mut();
@@ -470,6 +606,9 @@ impl Player {
             },
         );
 
+        let current_weapon = self.current_weapon();
+        let is_reloading = current_weapon.is_some() && weapons[current_weapon].is_reloading();
+
         self.upper_body_machine.apply(
             scene,
             time.delta,
@@ -479,6 +618,7 @@ impl Player {
                 has_ground_contact: self.in_air_time <= 0.3,
                 is_aiming: self.controller.aim,
                 toss_grenade: self.controller.toss_grenade,
+                is_reloading,
                 weapon: CombatWeaponKind::Rifle,
                 change_weapon: self.weapon_change_direction != Direction::None,
                 run_factor: self.run_factor,
@@ -604,7 +744,10 @@ impl Player {
                         position,
                         direction,
                         initial_velocity: direction.scale(15.0),
-                        owner: Default::default(),
+                        // Projectiles ignore owner of their weapon, so the grenade does not
+                        // hit the player right after the toss.
+                        owner: self.current_weapon(),
+                        who: self_handle,
                     })
                     .unwrap();
             }
@@ -797,17 +940,32 @@ impl Player {
                 (5.0, 10.0)
             };
 
-            self.target_camera_offset.x = 0.015 * (time.elapsed as f32 * kx).cos();
-            self.target_camera_offset.y = 0.015 * (time.elapsed as f32 * ky).sin();
+            self.target_camera_offset.x = 0.015 * (*level_time as f32 * kx).cos();
+            self.target_camera_offset.y = 0.015 * (*level_time as f32 * ky).sin();
         } else {
             self.target_camera_offset.x = 0.0;
             self.target_camera_offset.y = 0.0;
         }
 
+        if self.camera_shake > 0.0 {
+            let t = *level_time as f32;
+            self.target_camera_offset.x +=
+                CAMERA_SHAKE_AMPLITUDE * self.camera_shake * (t * 47.0).sin();
+            self.target_camera_offset.y +=
+                CAMERA_SHAKE_AMPLITUDE * self.camera_shake * (t * 53.0).cos();
+            self.camera_shake = (self.camera_shake - time.delta / CAMERA_SHAKE_DURATION).max(0.0);
+        }
+
         self.target_camera_offset.z = if self.controller.aim { 0.2 } else { 0.8 };
 
         for result in results {
-            if result.collider != self.collider {
+            // Hitboxes are sensors, camera goes through them.
+            let is_sensor = scene
+                .physics
+                .colliders
+                .get(result.collider.into())
+                .map_or(false, |c| c.is_sensor());
+            if result.collider != self.collider && !is_sensor {
                 let new_offset = (result.toi.min(0.8) - 0.2).max(0.1);
                 if new_offset < self.target_camera_offset.z {
                     self.target_camera_offset.z = new_offset;
@@ -841,6 +999,22 @@ impl Player {
             ));
 
         if has_ground_contact {
+            let safe_fall_time = self.definition.safe_fall_time;
+            if self.in_air_time > safe_fall_time {
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::DamageActor {
+                        actor: self_handle,
+                        who: Handle::NONE,
+                        amount: (self.in_air_time - safe_fall_time)
+                            * self.definition.fall_damage_per_second,
+                        damage_type: DamageType::Fall,
+                        position: None,
+                        location: None,
+                    })
+                    .unwrap();
+            }
             self.in_air_time = 0.0;
         } else {
             self.in_air_time += time.delta;
@@ -891,10 +1065,19 @@ impl Player {
         }
     }
 
-    pub fn process_input_event(&mut self, event: &Event<()>, dt: f32, scene: &mut Scene) {
+    /// Translates OS event into new input state and applies it. Returns new input state if it
+    /// was changed by the event, so it can be recorded.
+    pub fn process_input_event(
+        &mut self,
+        event: &Event<()>,
+        dt: f32,
+        scene: &mut Scene,
+    ) -> Option<InputController> {
         let scheme = self.control_scheme.clone().unwrap();
         let scheme = scheme.read().unwrap();
 
+        let mut input = self.controller.clone();
+
         let button_state = match event {
             Event::WindowEvent { event, .. } => {
                 if let WindowEvent::KeyboardInput { input, .. } = event {
@@ -927,8 +1110,8 @@ impl Player {
                 }
                 DeviceEvent::MouseMotion { delta } => {
                     let mouse_sens = scheme.mouse_sens * dt;
-                    self.controller.yaw -= (delta.0 as f32) * mouse_sens;
-                    self.controller.pitch = (self.controller.pitch + (delta.1 as f32) * mouse_sens)
+                    input.yaw -= (delta.0 as f32) * mouse_sens;
+                    input.pitch = (input.pitch + (delta.1 as f32) * mouse_sens)
                         .max(-90.0f32.to_radians())
                         .min(90.0f32.to_radians());
                     None
@@ -939,63 +1122,120 @@ impl Player {
         };
 
         if let Some((button, state)) = button_state {
+            let pressed = state == ElementState::Pressed;
             if button == scheme.aim.button {
-                self.controller.aim = state == ElementState::Pressed;
+                input.aim = pressed;
             } else if button == scheme.move_forward.button {
-                self.controller.walk_forward = state == ElementState::Pressed;
+                input.walk_forward = pressed;
             } else if button == scheme.move_backward.button {
-                self.controller.walk_backward = state == ElementState::Pressed;
+                input.walk_backward = pressed;
             } else if button == scheme.move_left.button {
-                self.controller.walk_left = state == ElementState::Pressed;
+                input.walk_left = pressed;
             } else if button == scheme.move_right.button {
-                self.controller.walk_right = state == ElementState::Pressed;
+                input.walk_right = pressed;
             } else if button == scheme.jump.button {
-                self.controller.jump = state == ElementState::Pressed;
+                input.jump = pressed;
             } else if button == scheme.run.button {
-                self.controller.run = state == ElementState::Pressed;
+                input.run = pressed;
             } else if button == scheme.next_weapon.button {
-                if state == ElementState::Pressed
-                    && self.current_weapon < self.weapons.len() as u32 - 1
-                {
-                    self.weapon_change_direction = Direction::Next;
-
-                    scene
-                        .animations
-                        .get_mut(self.upper_body_machine.put_back_animation)
-                        .rewind();
-
-                    scene
-                        .animations
-                        .get_mut(self.upper_body_machine.grab_animation)
-                        .set_enabled(false)
-                        .rewind();
-                }
+                input.next_weapon = pressed;
             } else if button == scheme.prev_weapon.button {
-                if state == ElementState::Pressed && self.current_weapon > 0 {
-                    self.weapon_change_direction = Direction::Previous;
-
-                    scene
-                        .animations
-                        .get_mut(self.upper_body_machine.put_back_animation)
-                        .rewind();
-
-                    scene
-                        .animations
-                        .get_mut(self.upper_body_machine.grab_animation)
-                        .set_enabled(false)
-                        .rewind();
-                }
+                input.prev_weapon = pressed;
             } else if button == scheme.toss_grenade.button {
-                self.controller.toss_grenade = state == ElementState::Pressed;
-                if state == ElementState::Pressed {
-                    scene
-                        .animations
-                        .get_mut(self.upper_body_machine.toss_grenade_animation)
-                        .rewind();
-                }
+                input.toss_grenade = pressed;
             } else if button == scheme.shoot.button {
-                self.controller.shoot = state == ElementState::Pressed;
+                input.shoot = pressed;
+            } else if button == scheme.interact.button {
+                input.interact = pressed;
+            } else if button == scheme.use_medkit.button {
+                input.use_medkit = pressed;
+            } else if button == scheme.reload.button {
+                input.reload = pressed;
+            }
+        }
+
+        if input != self.controller {
+            self.apply_input(input.clone(), scene);
+            Some(input)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true once after Use button was pressed.
+    pub fn take_use_request(&mut self) -> bool {
+        std::mem::replace(&mut self.use_requested, false)
+    }
+
+    /// Applies new input state. This is the only way how input gets into the player, both OS
+    /// events and replays come through here.
+    pub fn apply_input(&mut self, mut input: InputController, scene: &mut Scene) {
+        if input.next_weapon && self.current_weapon < self.weapons.len() as u32 - 1 {
+            self.weapon_change_direction = Direction::Next;
+
+            scene
+                .animations
+                .get_mut(self.upper_body_machine.put_back_animation)
+                .rewind();
+
+            scene
+                .animations
+                .get_mut(self.upper_body_machine.grab_animation)
+                .set_enabled(false)
+                .rewind();
+        } else if input.prev_weapon && self.current_weapon > 0 {
+            self.weapon_change_direction = Direction::Previous;
+
+            scene
+                .animations
+                .get_mut(self.upper_body_machine.put_back_animation)
+                .rewind();
+
+            scene
+                .animations
+                .get_mut(self.upper_body_machine.grab_animation)
+                .set_enabled(false)
+                .rewind();
+        }
+
+        if input.interact && !self.controller.interact {
+            self.use_requested = true;
+        }
+
+        if input.use_medkit && !self.controller.use_medkit {
+            if let Some(health) = self.character.inventory.take_medkit() {
+                self.character.heal(health as f32);
             }
         }
+
+        if input.reload && !self.controller.reload && self.current_weapon().is_some() {
+            self.character
+                .sender
+                .as_ref()
+                .unwrap()
+                .send(Message::ReloadWeapon {
+                    weapon: self.current_weapon(),
+                })
+                .unwrap();
+        }
+
+        if input.toss_grenade && !self.controller.toss_grenade {
+            // Grenade is tossed only from aim state of upper body.
+            if input.aim && self.character.inventory.take_grenade() {
+                scene
+                    .animations
+                    .get_mut(self.upper_body_machine.toss_grenade_animation)
+                    .rewind();
+            } else {
+                // Nothing to toss or the player is not aiming.
+                input.toss_grenade = false;
+            }
+        }
+
+        self.controller = InputController {
+            next_weapon: false,
+            prev_weapon: false,
+            ..input
+        };
     }
 }
@@ -1,6 +1,7 @@
 use crate::{
     create_play_animation_state,
     player::{make_walk_state, WalkStateDefinition},
+    save::visit_since,
 };
 use rg3d::{
     animation::{
@@ -15,12 +16,17 @@ use rg3d::{
     scene::{node::Node, Scene},
 };
 
+/// Placeholder, there is no dedicated reload animation for the agent model yet. Grab animation
+/// is played instead, replace the path once a reload animation is made.
+const RELOAD_ANIMATION: &str = "data/animations/agent_grab.fbx";
+
 #[derive(Default)]
 pub struct UpperBodyMachine {
     pub machine: Machine,
     pub aim_state: Handle<State>,
     pub toss_grenade_state: Handle<State>,
     pub put_back_state: Handle<State>,
+    pub reload_state: Handle<State>,
     pub jump_animation: Handle<Animation>,
     pub walk_animation: Handle<Animation>,
     pub run_animation: Handle<Animation>,
@@ -28,6 +34,7 @@ pub struct UpperBodyMachine {
     pub toss_grenade_animation: Handle<Animation>,
     pub put_back_animation: Handle<Animation>,
     pub grab_animation: Handle<Animation>,
+    pub reload_animation: Handle<Animation>,
 }
 
 fn disable_leg_tracks(
@@ -60,6 +67,8 @@ impl Visit for UpperBodyMachine {
         self.toss_grenade_state.visit("TossGrenadeState", visitor)?;
         self.put_back_animation.visit("PutBackAnimation", visitor)?;
         self.grab_animation.visit("GrabAnimation", visitor)?;
+        visit_since(&mut self.reload_state, "ReloadState", 12, visitor)?;
+        visit_since(&mut self.reload_animation, "ReloadAnimation", 12, visitor)?;
 
         visitor.leave_region()
     }
@@ -78,6 +87,7 @@ pub struct UpperBodyMachineInput {
     pub has_ground_contact: bool,
     pub is_aiming: bool,
     pub toss_grenade: bool,
+    pub is_reloading: bool,
     pub weapon: CombatWeaponKind,
     pub change_weapon: bool,
 }
@@ -111,6 +121,13 @@ impl UpperBodyMachine {
     const GRAB_TO_IDLE: &'static str = "GrabToIdle";
     const GRAB_TO_WALK: &'static str = "GrabToWalk";
 
+    const IDLE_TO_RELOAD: &'static str = "IdleToReload";
+    const WALK_TO_RELOAD: &'static str = "WalkToReload";
+    const AIM_TO_RELOAD: &'static str = "AimToReload";
+    const RELOAD_TO_IDLE: &'static str = "ReloadToIdle";
+    const RELOAD_TO_WALK: &'static str = "ReloadToWalk";
+    const RELOAD_TO_AIM: &'static str = "ReloadToAim";
+
     const RIFLE_AIM_FACTOR: &'static str = "RifleAimFactor";
     const PISTOL_AIM_FACTOR: &'static str = "PistolAimFactor";
 
@@ -126,7 +143,7 @@ impl UpperBodyMachine {
         scene: &mut Scene,
         model: Handle<Node>,
         resource_manager: ResourceManager,
-    ) -> Self {
+    ) -> Result<Self, String> {
         let mut machine = Machine::new();
 
         let (
@@ -141,6 +158,7 @@ impl UpperBodyMachine {
             put_back_animation_resource,
             grab_animation_resource,
             run_animation_resource,
+            reload_animation_resource,
         ) = rg3d::futures::join!(
             resource_manager.request_model("data/animations/agent_walk_rifle.fbx"),
             resource_manager.request_model("data/animations/agent_idle.fbx"),
@@ -153,6 +171,7 @@ impl UpperBodyMachine {
             resource_manager.request_model("data/animations/agent_put_back.fbx"),
             resource_manager.request_model("data/animations/agent_grab.fbx"),
             resource_manager.request_model("data/animations/agent_run_rifle.fbx"),
+            resource_manager.request_model(RELOAD_ANIMATION),
         );
 
         let aim_rifle_animation = *aim_rifle_animation_resource
@@ -189,7 +208,7 @@ impl UpperBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (idle_animation, idle_state) = create_play_animation_state(
             idle_animation_resource.unwrap(),
@@ -197,7 +216,7 @@ impl UpperBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (jump_animation, jump_state) = create_play_animation_state(
             jump_animation_resource.unwrap(),
@@ -205,7 +224,7 @@ impl UpperBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (fall_animation, fall_state) = create_play_animation_state(
             falling_animation_resource.unwrap(),
@@ -213,7 +232,7 @@ impl UpperBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (land_animation, land_state) = create_play_animation_state(
             landing_animation_resource.unwrap(),
@@ -221,7 +240,7 @@ impl UpperBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (put_back_animation, put_back_state) = create_play_animation_state(
             put_back_animation_resource.unwrap(),
@@ -229,7 +248,7 @@ impl UpperBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
 
         let (grab_animation, grab_state) = create_play_animation_state(
             grab_animation_resource.unwrap(),
@@ -237,7 +256,15 @@ impl UpperBodyMachine {
             &mut machine,
             scene,
             model,
-        );
+        )?;
+
+        let (reload_animation, reload_state) = create_play_animation_state(
+            reload_animation_resource.unwrap(),
+            "Reload",
+            &mut machine,
+            scene,
+            model,
+        )?;
 
         let WalkStateDefinition {
             walk_animation,
@@ -446,6 +473,21 @@ impl UpperBodyMachine {
             Self::GRAB_TO_WALK,
         ));
 
+        for (name, source, rule) in &[
+            ("Idle->Reload", idle_state, Self::IDLE_TO_RELOAD),
+            ("Walk->Reload", walk_state, Self::WALK_TO_RELOAD),
+            ("Aim->Reload", aim_state, Self::AIM_TO_RELOAD),
+        ] {
+            machine.add_transition(Transition::new(name, *source, reload_state, 0.20, rule));
+        }
+        for (name, dest, rule) in &[
+            ("Reload->Idle", idle_state, Self::RELOAD_TO_IDLE),
+            ("Reload->Walk", walk_state, Self::RELOAD_TO_WALK),
+            ("Reload->Aim", aim_state, Self::RELOAD_TO_AIM),
+        ] {
+            machine.add_transition(Transition::new(name, reload_state, *dest, 0.20, rule));
+        }
+
         for leg in &["mixamorig:LeftUpLeg", "mixamorig:RightUpLeg"] {
             for &animation in &[
                 aim_pistol_animation,
@@ -459,6 +501,7 @@ impl UpperBodyMachine {
                 grab_animation,
                 put_back_animation,
                 run_animation,
+                reload_animation,
             ] {
                 disable_leg_tracks(animation, model, leg, scene);
             }
@@ -466,11 +509,12 @@ impl UpperBodyMachine {
 
         machine.set_entry_state(idle_state);
 
-        Self {
+        Ok(Self {
             machine,
             aim_state,
             toss_grenade_state,
             put_back_state,
+            reload_state,
             jump_animation,
             walk_animation,
             run_animation,
@@ -478,7 +522,8 @@ impl UpperBodyMachine {
             toss_grenade_animation,
             put_back_animation,
             grab_animation,
-        }
+            reload_animation,
+        })
     }
 
     pub fn apply(&mut self, scene: &mut Scene, dt: f32, input: UpperBodyMachineInput) {
@@ -598,6 +643,21 @@ impl UpperBodyMachine {
                 Self::AIM_TO_TOSS_GRENADE,
                 Parameter::Rule(input.toss_grenade && input.is_aiming),
             )
+            .set_parameter(Self::IDLE_TO_RELOAD, Parameter::Rule(input.is_reloading))
+            .set_parameter(Self::WALK_TO_RELOAD, Parameter::Rule(input.is_reloading))
+            .set_parameter(Self::AIM_TO_RELOAD, Parameter::Rule(input.is_reloading))
+            .set_parameter(
+                Self::RELOAD_TO_IDLE,
+                Parameter::Rule(!input.is_reloading && !input.is_walking && !input.is_aiming),
+            )
+            .set_parameter(
+                Self::RELOAD_TO_WALK,
+                Parameter::Rule(!input.is_reloading && input.is_walking && !input.is_aiming),
+            )
+            .set_parameter(
+                Self::RELOAD_TO_AIM,
+                Parameter::Rule(!input.is_reloading && input.is_aiming),
+            )
             .evaluate_pose(&scene.animations, dt)
             .apply_with(&mut scene.graph, |node, pose| {
                 if node.name() == "mixamorig:Hips" {
@@ -0,0 +1,100 @@
+//! Replay is a recording of every change of player's input with index of fixed step at which
+//! the change has happened. Together with level id and seed of random number generator it is
+//! enough to reproduce whole session, since simulation is deterministic.
+
+use crate::player::InputController;
+use serde::{Deserialize, Serialize};
+use std::{fs::File, path::Path};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayFrame {
+    /// Index of fixed step at which input was changed.
+    pub tick: u64,
+    pub input: InputController,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Replay {
+    pub level: String,
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(level: String, seed: u64) -> Self {
+        Self {
+            level,
+            seed,
+            frames: Default::default(),
+        }
+    }
+
+    pub fn push(&mut self, tick: u64, input: InputController) {
+        self.frames.push(ReplayFrame { tick, input });
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let file = File::create(path.as_ref()).map_err(|e| {
+            format!(
+                "Unable to create replay file {}. Reason: {}",
+                path.as_ref().display(),
+                e
+            )
+        })?;
+        ron::ser::to_writer_pretty(file, self, Default::default()).map_err(|e| {
+            format!(
+                "Unable to write replay file {}. Reason: {}",
+                path.as_ref().display(),
+                e
+            )
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let file = File::open(path.as_ref()).map_err(|e| {
+            format!(
+                "Unable to open replay file {}. Reason: {}",
+                path.as_ref().display(),
+                e
+            )
+        })?;
+        ron::de::from_reader(file).map_err(|e| {
+            format!(
+                "Unable to parse replay file {}. Reason: {}",
+                path.as_ref().display(),
+                e
+            )
+        })
+    }
+}
+
+/// Feeds recorded inputs back in the same order and at the same fixed steps.
+pub struct ReplayPlayer {
+    replay: Replay,
+    position: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            position: 0,
+        }
+    }
+
+    /// Returns next recorded input for given tick, if any. Must be called repeatedly until it
+    /// returns `None`, because there could be multiple input changes during one step.
+    pub fn next_input(&mut self, tick: u64) -> Option<InputController> {
+        let frame = self.replay.frames.get(self.position)?;
+        if frame.tick <= tick {
+            self.position += 1;
+            Some(frame.input.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.replay.frames.len()
+    }
+}
@@ -0,0 +1,224 @@
+//! Results screen is shown when a level ends, it shows outcome and statistics of the level
+//! and lets the player retry the level, go to the next level or return to main menu.
+
+use crate::{
+    level::stats::{LevelStats, Outcome},
+    message::Message,
+    GameEngine, Gui, GuiMessage, UiNode,
+};
+use rg3d::{
+    core::pool::Handle,
+    gui::{
+        button::ButtonBuilder,
+        grid::{Column, GridBuilder, Row},
+        message::{
+            ButtonMessage, MessageDirection, TextMessage, UiMessageData, WidgetMessage,
+            WindowMessage,
+        },
+        text::TextBuilder,
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        Thickness,
+    },
+};
+use std::{fmt::Write, sync::mpsc::Sender};
+
+pub struct ResultsScreen {
+    pub window: Handle<UiNode>,
+    sender: Sender<Message>,
+    text: Handle<UiNode>,
+    btn_retry: Handle<UiNode>,
+    btn_next_level: Handle<UiNode>,
+    btn_main_menu: Handle<UiNode>,
+    level: String,
+    next_level: Option<String>,
+    /// Seed for the level that is loaded from the screen, it is taken from the finished level.
+    seed: u64,
+}
+
+impl ResultsScreen {
+    pub fn new(engine: &mut GameEngine, sender: Sender<Message>) -> Self {
+        let ctx = &mut engine.user_interface.build_ctx();
+
+        let margin = Thickness::uniform(2.0);
+
+        let text;
+        let btn_retry;
+        let btn_next_level;
+        let btn_main_menu;
+        let window = WindowBuilder::new(WidgetBuilder::new().with_width(400.0).with_height(320.0))
+            .can_close(false)
+            .can_minimize(false)
+            .open(false)
+            .with_content(
+                GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_margin(Thickness::uniform(5.0))
+                        .with_child({
+                            text = TextBuilder::new(
+                                WidgetBuilder::new().on_row(0).with_margin(margin),
+                            )
+                            .build(ctx);
+                            text
+                        })
+                        .with_child(
+                            GridBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(1)
+                                    .with_child({
+                                        btn_retry = ButtonBuilder::new(
+                                            WidgetBuilder::new().on_column(0).with_margin(margin),
+                                        )
+                                        .with_text("Retry")
+                                        .build(ctx);
+                                        btn_retry
+                                    })
+                                    .with_child({
+                                        btn_next_level = ButtonBuilder::new(
+                                            WidgetBuilder::new().on_column(1).with_margin(margin),
+                                        )
+                                        .with_text("Next Level")
+                                        .build(ctx);
+                                        btn_next_level
+                                    })
+                                    .with_child({
+                                        btn_main_menu = ButtonBuilder::new(
+                                            WidgetBuilder::new().on_column(2).with_margin(margin),
+                                        )
+                                        .with_text("Main Menu")
+                                        .build(ctx);
+                                        btn_main_menu
+                                    }),
+                            )
+                            .add_row(Row::stretch())
+                            .add_column(Column::stretch())
+                            .add_column(Column::stretch())
+                            .add_column(Column::stretch())
+                            .build(ctx),
+                        ),
+                )
+                .add_row(Row::stretch())
+                .add_row(Row::strict(36.0))
+                .add_column(Column::stretch())
+                .build(ctx),
+            )
+            .build(ctx);
+
+        Self {
+            window,
+            sender,
+            text,
+            btn_retry,
+            btn_next_level,
+            btn_main_menu,
+            level: Default::default(),
+            next_level: None,
+            seed: 0,
+        }
+    }
+
+    /// Opens results of a level, next level can be selected only after victory.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        &mut self,
+        ui: &mut Gui,
+        outcome: Outcome,
+        time: f32,
+        stats: &LevelStats,
+        level: String,
+        next_level: Option<String>,
+        seed: u64,
+    ) {
+        self.level = level;
+        self.seed = seed;
+        self.next_level = if outcome == Outcome::Victory {
+            next_level
+        } else {
+            None
+        };
+
+        ui.send_message(WindowMessage::title(
+            self.window,
+            MessageDirection::ToWidget,
+            WindowTitle::text(outcome.description()),
+        ));
+        ui.send_message(TextMessage::text(
+            self.text,
+            MessageDirection::ToWidget,
+            format_stats(time, stats),
+        ));
+        ui.send_message(WidgetMessage::visibility(
+            self.btn_next_level,
+            MessageDirection::ToWidget,
+            self.next_level.is_some(),
+        ));
+        ui.send_message(WindowMessage::open(
+            self.window,
+            MessageDirection::ToWidget,
+            true,
+        ));
+    }
+
+    pub fn close(&mut self, ui: &mut Gui) {
+        ui.send_message(WindowMessage::close(
+            self.window,
+            MessageDirection::ToWidget,
+        ));
+    }
+
+    pub fn is_open(&self, ui: &Gui) -> bool {
+        ui.node(self.window).visibility()
+    }
+
+    pub fn handle_ui_event(&mut self, engine: &mut GameEngine, message: &GuiMessage) {
+        if let UiMessageData::Button(ButtonMessage::Click) = message.data() {
+            let response = if message.destination() == self.btn_retry {
+                Some(Message::LoadLevel {
+                    id: self.level.clone(),
+                    seed: self.seed,
+                })
+            } else if message.destination() == self.btn_next_level {
+                let seed = self.seed;
+                self.next_level
+                    .clone()
+                    .map(|id| Message::LoadLevel { id, seed })
+            } else if message.destination() == self.btn_main_menu {
+                Some(Message::ShowMainMenu)
+            } else {
+                None
+            };
+
+            if let Some(response) = response {
+                self.sender.send(response).unwrap();
+                self.close(&mut engine.user_interface);
+            }
+        }
+    }
+}
+
+fn format_stats(time: f32, stats: &LevelStats) -> String {
+    let mut text = String::new();
+    let seconds = time as u32;
+    writeln!(text, "Time: {}:{:02}", seconds / 60, seconds % 60).unwrap();
+    writeln!(text, "Kills:").unwrap();
+    let mut any_kills = false;
+    for (kind, count) in stats.kills() {
+        writeln!(text, "    {}: {}", kind.description(), count).unwrap();
+        any_kills = true;
+    }
+    if !any_kills {
+        writeln!(text, "    None").unwrap();
+    }
+    writeln!(
+        text,
+        "Accuracy: {:.0}% ({}/{})",
+        stats.accuracy() * 100.0,
+        stats.shots_hit,
+        stats.shots_fired
+    )
+    .unwrap();
+    writeln!(text, "Headshots: {}", stats.headshots).unwrap();
+    writeln!(text, "Damage taken: {:.0}", stats.damage_taken).unwrap();
+    write!(text, "Items collected: {}", stats.items_collected).unwrap();
+    text
+}
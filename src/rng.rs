@@ -0,0 +1,79 @@
+//! Game uses single seeded random number generator for everything that affects simulation,
+//! so same seed and same inputs always produce same simulation. This is essential for bug
+//! reproduction and replays. Generator state is saved together with level.
+
+use rg3d::{
+    core::visitor::{Visit, VisitResult, Visitor},
+    rand::{self, RngCore},
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// SplitMix64 generator. It is not cryptographically secure, but it is fast, has decent
+/// statistical quality and its whole state is a single number which is trivial to save.
+pub struct GameRng {
+    seed: u64,
+    state: u64,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// Creates new seed from current system time. Use it when determinism is not required,
+    /// but keep the seed around (log it for example) to be able to reproduce a session.
+    pub fn time_seed() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default()
+    }
+
+    /// Returns seed with which generator was created.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl Visit for GameRng {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.seed.visit("Seed", visitor)?;
+        self.state.visit("State", visitor)?;
+
+        visitor.leave_region()
+    }
+}
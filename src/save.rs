@@ -0,0 +1,418 @@
+//! Save slots. Every slot is a pair of files in `saves` directory: binary game state written
+//! by `Visitor` and a small metadata file which is used by save browser, so it does not have
+//! to load whole game state just to show a list of saves.
+//!
+//! Binary state starts with a header which contains version of save format. Every time when
+//! layout of saved data changes, `SAVE_VERSION` must be increased, new fields must be visited
+//! using `visit_since` and a migration must be registered in `MIGRATIONS` if state that is
+//! missing in older saves can't be left default.
+
+use crate::level::{manifest::LevelManifest, Level};
+use rg3d::{
+    core::visitor::{Visit, VisitResult, Visitor},
+    utils::log::{Log, MessageKind},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    fs::{self, File},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const SAVES_DIR: &str = "saves";
+
+/// Amount of rotating autosave slots.
+pub const AUTOSAVE_SLOTS: usize = 3;
+
+/// Current version of save format.
+///
+/// History:
+/// 0 - initial format without header.
+/// 1 - header, level id, next level, level exits, random number generator and tick of level.
+/// 2 - checkpoints and respawn position of level.
+/// 3 - per-instance parameters of spawn points and items.
+/// 4 - triggers instead of death zones, groups of spawn points.
+/// 5 - doors, elevators and keycards of characters.
+/// 6 - encounter director and respawn state of spawn points.
+/// 7 - objectives of level, older saves have no objectives.
+/// 8 - statistics of level.
+/// 9 - lifetime and respawn timer of items.
+/// 10 - inventory of characters, keycards are moved into it.
+/// 11 - armor tier of characters.
+/// 12 - reload state of weapons and reload state of upper body machine of the player.
+/// 13 - spread of weapons.
+/// 14 - hitboxes of characters and headshots in statistics of level.
+/// 15 - actor who fired a projectile.
+/// 16 - occupancy of areas of objectives.
+/// 17 - time of last shot of weapons is relative to start of level.
+pub const SAVE_VERSION: u32 = 17;
+
+thread_local! {
+    static READ_VERSION: Cell<u32> = Cell::new(SAVE_VERSION);
+}
+
+/// Returns version of a save that is being read on current thread.
+pub fn read_version() -> u32 {
+    READ_VERSION.with(|v| v.get())
+}
+
+/// Sets version of a save that is being read on current thread, previous value is restored
+/// when the scope is dropped.
+pub struct ReadVersionScope {
+    prev: u32,
+}
+
+impl ReadVersionScope {
+    pub fn new(version: u32) -> Self {
+        Self {
+            prev: READ_VERSION.with(|v| v.replace(version)),
+        }
+    }
+}
+
+impl Drop for ReadVersionScope {
+    fn drop(&mut self) {
+        READ_VERSION.with(|v| v.set(self.prev));
+    }
+}
+
+/// Visits a field that was added to save format in given version. Saves of older versions
+/// do not have the field, so it keeps its current (default) value when reading such saves.
+pub fn visit_since<T: Visit + ?Sized>(
+    value: &mut T,
+    name: &str,
+    version: u32,
+    visitor: &mut Visitor,
+) -> VisitResult {
+    if visitor.is_reading() && read_version() < version {
+        Ok(())
+    } else {
+        value.visit(name, visitor)
+    }
+}
+
+#[derive(Default)]
+pub struct SaveHeader {
+    pub version: u32,
+}
+
+impl SaveHeader {
+    pub fn current() -> Self {
+        Self {
+            version: SAVE_VERSION,
+        }
+    }
+
+    /// Reads header of a save, saves without header have version 0.
+    pub fn read(visitor: &mut Visitor) -> Self {
+        let mut header = Self::default();
+        if header.visit("Header", visitor).is_err() {
+            header.version = 0;
+        }
+        header
+    }
+
+    /// Checks if save with this header can be loaded by current version of the game.
+    pub fn check_compatibility(&self) -> Result<(), String> {
+        if self.version > SAVE_VERSION {
+            Err(format!(
+                "Save was made by newer version of the game (save format {}, supported {}).",
+                self.version, SAVE_VERSION
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Visit for SaveHeader {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.version.visit("Version", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Migration fixes up state that is missing in saves of older versions.
+pub struct Migration {
+    /// Version of save format which introduced the change.
+    pub version: u32,
+    pub description: &'static str,
+    pub upgrade: fn(&mut Level, &LevelManifest),
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Restore level identity from level manifest",
+        upgrade: restore_level_identity,
+    },
+    Migration {
+        version: 6,
+        description: "Restore encounter director settings from level manifest",
+        upgrade: restore_director_settings,
+    },
+    Migration {
+        version: 17,
+        description: "Reset time of last shot of weapons",
+        upgrade: reset_shot_times,
+    },
+];
+
+fn restore_level_identity(level: &mut Level, manifest: &LevelManifest) {
+    // Saves without header were made when there was only one level.
+    if let Some(definition) = manifest.definition(&manifest.start) {
+        level.assign_definition(definition);
+    }
+}
+
+fn restore_director_settings(level: &mut Level, manifest: &LevelManifest) {
+    if let Some(definition) = manifest.definition(&level.id) {
+        level.assign_definition(definition);
+    }
+}
+
+fn reset_shot_times(level: &mut Level, _manifest: &LevelManifest) {
+    // Older saves have time since start of the game, which can be far ahead of level time.
+    for weapon in level.weapons_mut().iter_mut() {
+        weapon.reset_shot_time();
+    }
+}
+
+/// Applies every migration newer than version of loaded save.
+pub fn migrate(level: &mut Level, manifest: &LevelManifest, version: u32) {
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        Log::writeln(
+            MessageKind::Information,
+            format!(
+                "Migrating save to version {}: {}",
+                migration.version, migration.description
+            ),
+        );
+        (migration.upgrade)(level, manifest);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveMetadata {
+    /// Identifier of a level from level manifest.
+    pub level: String,
+    /// Time in seconds spent on the level.
+    pub play_time: f32,
+    pub health: f32,
+    /// Names of weapons the player had.
+    pub weapons: Vec<String>,
+    /// Seconds since UNIX epoch.
+    pub timestamp: u64,
+    /// Version of save format.
+    #[serde(default)]
+    pub version: u32,
+}
+
+impl SaveMetadata {
+    pub fn now(level: String, play_time: f32, health: f32, weapons: Vec<String>) -> Self {
+        Self {
+            level,
+            play_time,
+            health,
+            weapons,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            version: SAVE_VERSION,
+        }
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.version <= SAVE_VERSION
+    }
+
+    /// Short human-readable description of the save, used in save browser.
+    pub fn description(&self) -> String {
+        let play_time = self.play_time as u64;
+        format!(
+            "{}{} | {:02}:{:02}:{:02} | HP {} | {} | {}",
+            if self.is_compatible() {
+                ""
+            } else {
+                "[INCOMPATIBLE] "
+            },
+            self.level,
+            play_time / 3600,
+            (play_time / 60) % 60,
+            play_time % 60,
+            self.health.max(0.0) as u32,
+            self.weapons.join(", "),
+            format_timestamp(self.timestamp)
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SaveSlot {
+    pub name: String,
+    pub metadata: SaveMetadata,
+}
+
+impl SaveSlot {
+    /// Turns arbitrary user input into a name that is safe to use as a file name.
+    pub fn sanitize_name(name: &str) -> String {
+        name.trim()
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    pub fn data_path(name: &str) -> PathBuf {
+        PathBuf::from(SAVES_DIR).join(format!("{}.bin", name))
+    }
+
+    pub fn debug_path(name: &str) -> PathBuf {
+        PathBuf::from(SAVES_DIR).join(format!("{}.txt", name))
+    }
+
+    pub fn metadata_path(name: &str) -> PathBuf {
+        PathBuf::from(SAVES_DIR).join(format!("{}.meta.ron", name))
+    }
+
+    pub fn exists(name: &str) -> bool {
+        Self::data_path(name).exists()
+    }
+
+    /// Makes sure that saves directory exists, must be called before writing a save.
+    pub fn prepare_dir() -> Result<(), String> {
+        fs::create_dir_all(SAVES_DIR)
+            .map_err(|e| format!("Unable to create saves directory. Reason: {}", e))
+    }
+
+    pub fn write_metadata(name: &str, metadata: &SaveMetadata) -> Result<(), String> {
+        let path = Self::metadata_path(name);
+        let file = File::create(&path).map_err(|e| {
+            format!(
+                "Unable to create save metadata {}. Reason: {}",
+                path.display(),
+                e
+            )
+        })?;
+        ron::ser::to_writer_pretty(file, metadata, Default::default()).map_err(|e| {
+            format!(
+                "Unable to write save metadata {}. Reason: {}",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    pub fn read_metadata(name: &str) -> Result<SaveMetadata, String> {
+        let path = Self::metadata_path(name);
+        let file = File::open(&path).map_err(|e| {
+            format!(
+                "Unable to open save metadata {}. Reason: {}",
+                path.display(),
+                e
+            )
+        })?;
+        ron::de::from_reader(file).map_err(|e| {
+            format!(
+                "Unable to parse save metadata {}. Reason: {}",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    /// Returns every save slot with valid metadata, most recent first.
+    pub fn list() -> Vec<SaveSlot> {
+        let mut slots = Vec::new();
+        if let Ok(entries) = fs::read_dir(SAVES_DIR) {
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                if let Some(name) = file_name.strip_suffix(".meta.ron") {
+                    if let Ok(metadata) = Self::read_metadata(name) {
+                        if Self::exists(name) {
+                            slots.push(SaveSlot {
+                                name: name.to_owned(),
+                                metadata,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        slots.sort_by(|a, b| b.metadata.timestamp.cmp(&a.metadata.timestamp));
+        slots
+    }
+
+    /// Returns name of autosave slot that should be written next: first empty one or the
+    /// oldest one.
+    pub fn next_autosave_name() -> String {
+        let mut oldest: Option<(String, u64)> = None;
+        for i in 1..=AUTOSAVE_SLOTS {
+            let name = format!("Autosave {}", i);
+            match Self::read_metadata(&name) {
+                Ok(metadata) => {
+                    if oldest
+                        .as_ref()
+                        .map_or(true, |(_, t)| metadata.timestamp < *t)
+                    {
+                        oldest = Some((name, metadata.timestamp));
+                    }
+                }
+                Err(_) => return name,
+            }
+        }
+        oldest.map(|(name, _)| name).unwrap()
+    }
+
+    pub fn delete(name: &str) -> Result<(), String> {
+        for path in [
+            Self::data_path(name),
+            Self::metadata_path(name),
+            Self::debug_path(name),
+        ]
+        .iter()
+        {
+            if path.exists() {
+                fs::remove_file(path)
+                    .map_err(|e| format!("Unable to delete {}. Reason: {}", path.display(), e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats UNIX timestamp as `YYYY-MM-DD HH:MM` in UTC.
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // Converts days since epoch to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        (seconds / 60) % 60
+    )
+}
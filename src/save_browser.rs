@@ -0,0 +1,342 @@
+use crate::{
+    gui::create_scroll_viewer,
+    message::Message,
+    save::{SaveMetadata, SaveSlot},
+    GameEngine, Gui, GuiMessage, UiNode,
+};
+use rg3d::{
+    core::pool::Handle,
+    gui::{
+        border::BorderBuilder,
+        button::ButtonBuilder,
+        decorator::DecoratorBuilder,
+        grid::{Column, GridBuilder, Row},
+        list_view::ListViewBuilder,
+        message::{
+            ButtonMessage, ListViewMessage, MessageBoxMessage, MessageDirection, TextBoxMessage,
+            UiMessageData, WidgetMessage, WindowMessage,
+        },
+        messagebox::{MessageBoxBuilder, MessageBoxButtons, MessageBoxResult},
+        text::TextBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        Thickness, VerticalAlignment,
+    },
+    utils::log::{Log, MessageKind},
+};
+use std::sync::mpsc::Sender;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum SaveBrowserMode {
+    Save,
+    Load,
+}
+
+/// Action that waits for user confirmation.
+enum PendingAction {
+    Overwrite(String),
+    Delete(String),
+}
+
+pub struct SaveBrowser {
+    pub window: Handle<UiNode>,
+    sender: Sender<Message>,
+    lv_slots: Handle<UiNode>,
+    tb_name: Handle<UiNode>,
+    btn_save: Handle<UiNode>,
+    btn_load: Handle<UiNode>,
+    btn_delete: Handle<UiNode>,
+    confirmation: Handle<UiNode>,
+    slots: Vec<SaveSlot>,
+    selection: Option<usize>,
+    name: String,
+    pending_action: Option<PendingAction>,
+}
+
+impl SaveBrowser {
+    pub fn new(engine: &mut GameEngine, sender: Sender<Message>) -> Self {
+        let ctx = &mut engine.user_interface.build_ctx();
+
+        let margin = Thickness::uniform(2.0);
+
+        let lv_slots;
+        let tb_name;
+        let btn_save;
+        let btn_load;
+        let btn_delete;
+        let window = WindowBuilder::new(WidgetBuilder::new().with_width(600.0).with_height(400.0))
+            .with_title(WindowTitle::text("Saves"))
+            .open(false)
+            .with_content(
+                GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_margin(Thickness::uniform(5.0))
+                        .with_child({
+                            lv_slots = ListViewBuilder::new(
+                                WidgetBuilder::new().on_row(0).with_margin(margin),
+                            )
+                            .with_scroll_viewer(create_scroll_viewer(ctx))
+                            .build(ctx);
+                            lv_slots
+                        })
+                        .with_child({
+                            tb_name = TextBoxBuilder::new(
+                                WidgetBuilder::new().on_row(1).with_margin(margin),
+                            )
+                            .with_vertical_text_alignment(VerticalAlignment::Center)
+                            .build(ctx);
+                            tb_name
+                        })
+                        .with_child(
+                            GridBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(2)
+                                    .with_child({
+                                        btn_save = ButtonBuilder::new(
+                                            WidgetBuilder::new().on_column(0).with_margin(margin),
+                                        )
+                                        .with_text("Save")
+                                        .build(ctx);
+                                        btn_save
+                                    })
+                                    .with_child({
+                                        btn_load = ButtonBuilder::new(
+                                            WidgetBuilder::new().on_column(0).with_margin(margin),
+                                        )
+                                        .with_text("Load")
+                                        .build(ctx);
+                                        btn_load
+                                    })
+                                    .with_child({
+                                        btn_delete = ButtonBuilder::new(
+                                            WidgetBuilder::new().on_column(1).with_margin(margin),
+                                        )
+                                        .with_text("Delete")
+                                        .build(ctx);
+                                        btn_delete
+                                    }),
+                            )
+                            .add_row(Row::stretch())
+                            .add_column(Column::stretch())
+                            .add_column(Column::stretch())
+                            .build(ctx),
+                        ),
+                )
+                .add_row(Row::stretch())
+                .add_row(Row::strict(30.0))
+                .add_row(Row::strict(36.0))
+                .add_column(Column::stretch())
+                .build(ctx),
+            )
+            .build(ctx);
+
+        let confirmation = MessageBoxBuilder::new(
+            WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(120.0))
+                .open(false)
+                .with_title(WindowTitle::text("Confirm")),
+        )
+        .with_buttons(MessageBoxButtons::YesNo)
+        .build(ctx);
+
+        Self {
+            window,
+            sender,
+            lv_slots,
+            tb_name,
+            btn_save,
+            btn_load,
+            btn_delete,
+            confirmation,
+            slots: Default::default(),
+            selection: None,
+            name: Default::default(),
+            pending_action: None,
+        }
+    }
+
+    /// Opens browser in given mode and re-reads list of save slots.
+    pub fn open(&mut self, ui: &mut Gui, mode: SaveBrowserMode) {
+        let title = match mode {
+            SaveBrowserMode::Save => "Save Game",
+            SaveBrowserMode::Load => "Load Game",
+        };
+        ui.send_message(WindowMessage::title(
+            self.window,
+            MessageDirection::ToWidget,
+            WindowTitle::text(title),
+        ));
+
+        let is_save = mode == SaveBrowserMode::Save;
+        for &(widget, visibility) in &[
+            (self.tb_name, is_save),
+            (self.btn_save, is_save),
+            (self.btn_load, !is_save),
+        ] {
+            ui.send_message(WidgetMessage::visibility(
+                widget,
+                MessageDirection::ToWidget,
+                visibility,
+            ));
+        }
+
+        self.refresh(ui);
+
+        ui.send_message(WindowMessage::open(
+            self.window,
+            MessageDirection::ToWidget,
+            true,
+        ));
+    }
+
+    pub fn close(&mut self, ui: &mut Gui) {
+        ui.send_message(WindowMessage::close(
+            self.window,
+            MessageDirection::ToWidget,
+        ));
+        ui.send_message(WindowMessage::close(
+            self.confirmation,
+            MessageDirection::ToWidget,
+        ));
+        self.pending_action = None;
+    }
+
+    fn refresh(&mut self, ui: &mut Gui) {
+        self.slots = SaveSlot::list();
+        self.selection = None;
+
+        let items = self
+            .slots
+            .iter()
+            .map(|slot| make_slot_item(ui, &slot.name, &slot.metadata))
+            .collect();
+
+        ui.send_message(ListViewMessage::items(
+            self.lv_slots,
+            MessageDirection::ToWidget,
+            items,
+        ));
+    }
+
+    fn ask_confirmation(&mut self, ui: &mut Gui, text: String, action: PendingAction) {
+        self.pending_action = Some(action);
+        ui.send_message(MessageBoxMessage::open(
+            self.confirmation,
+            MessageDirection::ToWidget,
+            None,
+            Some(text),
+        ));
+    }
+
+    fn save(&mut self, ui: &mut Gui, name: String) {
+        self.sender.send(Message::SaveGame { slot: name }).unwrap();
+        self.close(ui);
+    }
+
+    fn delete(&mut self, ui: &mut Gui, name: String) {
+        if let Err(e) = SaveSlot::delete(&name) {
+            Log::writeln(MessageKind::Error, e);
+        }
+        self.refresh(ui);
+    }
+
+    pub fn handle_ui_event(&mut self, engine: &mut GameEngine, message: &GuiMessage) {
+        let ui = &mut engine.user_interface;
+
+        match message.data() {
+            UiMessageData::ListView(ListViewMessage::SelectionChanged(selection))
+                if message.destination() == self.lv_slots =>
+            {
+                self.selection = *selection;
+                if let Some(slot) = selection.and_then(|i| self.slots.get(i)) {
+                    self.name = slot.name.clone();
+                    ui.send_message(TextBoxMessage::text(
+                        self.tb_name,
+                        MessageDirection::ToWidget,
+                        slot.name.clone(),
+                    ));
+                }
+            }
+            UiMessageData::TextBox(TextBoxMessage::Text(text))
+                if message.destination() == self.tb_name =>
+            {
+                self.name = text.clone();
+            }
+            UiMessageData::Button(ButtonMessage::Click) => {
+                if message.destination() == self.btn_save {
+                    let name = SaveSlot::sanitize_name(&self.name);
+                    if !name.is_empty() {
+                        if SaveSlot::exists(&name) {
+                            self.ask_confirmation(
+                                ui,
+                                format!("Overwrite save \"{}\"?", name),
+                                PendingAction::Overwrite(name),
+                            );
+                        } else {
+                            self.save(ui, name);
+                        }
+                    }
+                } else if message.destination() == self.btn_load {
+                    if let Some(slot) = self.selection.and_then(|i| self.slots.get(i)) {
+                        self.sender
+                            .send(Message::LoadGame {
+                                slot: slot.name.clone(),
+                            })
+                            .unwrap();
+                        self.close(ui);
+                    }
+                } else if message.destination() == self.btn_delete {
+                    if let Some(slot) = self.selection.and_then(|i| self.slots.get(i)) {
+                        let name = slot.name.clone();
+                        self.ask_confirmation(
+                            ui,
+                            format!("Delete save \"{}\"?", name),
+                            PendingAction::Delete(name),
+                        );
+                    }
+                }
+            }
+            UiMessageData::MessageBox(MessageBoxMessage::Close(result))
+                if message.destination() == self.confirmation =>
+            {
+                if let Some(action) = self.pending_action.take() {
+                    if *result == MessageBoxResult::Yes {
+                        match action {
+                            PendingAction::Overwrite(name) => self.save(ui, name),
+                            PendingAction::Delete(name) => self.delete(ui, name),
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn make_slot_item(ui: &mut Gui, name: &str, metadata: &SaveMetadata) -> Handle<UiNode> {
+    let ctx = &mut ui.build_ctx();
+
+    DecoratorBuilder::new(BorderBuilder::new(
+        WidgetBuilder::new().with_child(
+            GridBuilder::new(
+                WidgetBuilder::new()
+                    .with_margin(Thickness::uniform(2.0))
+                    .with_child(
+                        TextBuilder::new(WidgetBuilder::new().on_row(0))
+                            .with_text(name)
+                            .build(ctx),
+                    )
+                    .with_child(
+                        TextBuilder::new(WidgetBuilder::new().on_row(1))
+                            .with_text(metadata.description().as_str())
+                            .build(ctx),
+                    ),
+            )
+            .add_row(Row::strict(22.0))
+            .add_row(Row::strict(22.0))
+            .add_column(Column::stretch())
+            .build(ctx),
+        ),
+    ))
+    .build(ctx)
+}
@@ -1,10 +1,17 @@
 use crate::{
-    actor::Actor, actor::ActorContainer, message::Message, weapon::projectile::ProjectileKind,
-    GameTime,
+    actor::{Actor, ActorContainer},
+    definition::{self, Definition, Storage},
+    hitbox::HitLocation,
+    inventory::AmmoKind,
+    item::ItemKind,
+    message::Message,
+    rng::GameRng,
+    save::visit_since,
+    weapon::projectile::ProjectileKind,
 };
 use rg3d::{
     core::{
-        algebra::{Matrix3, Vector3},
+        algebra::{Matrix3, Unit, UnitQuaternion, Vector3},
         color::Color,
         math::{ray::Ray, Matrix4Ext, Vector3Ext},
         pool::{Handle, Pool, PoolIteratorMut},
@@ -12,7 +19,7 @@ use rg3d::{
     },
     engine::resource_manager::ResourceManager,
     physics::geometry::InteractionGroups,
-    rand::seq::SliceRandom,
+    rand::{seq::SliceRandom, Rng},
     scene::{
         base::BaseBuilder,
         graph::Graph,
@@ -27,37 +34,58 @@ use rg3d::{
         log::{Log, MessageKind},
     },
 };
+use serde::Deserialize;
 use std::{
     hash::{Hash, Hasher},
     ops::{Index, IndexMut},
     path::{Path, PathBuf},
-    sync::mpsc::Sender,
+    str::FromStr,
+    sync::{mpsc::Sender, Arc, RwLock},
 };
 
 pub mod projectile;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-pub enum WeaponKind {
-    M4,
-    Ak47,
-    PlasmaRifle,
-}
+/// Id of a weapon definition, definitions are loaded from `data/weapons`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct WeaponKind(u32);
 
 impl WeaponKind {
     pub fn id(self) -> u32 {
-        match self {
-            WeaponKind::M4 => 0,
-            WeaponKind::Ak47 => 1,
-            WeaponKind::PlasmaRifle => 2,
-        }
+        self.0
     }
 
     pub fn new(id: u32) -> Result<Self, String> {
-        match id {
-            0 => Ok(WeaponKind::M4),
-            1 => Ok(WeaponKind::Ak47),
-            2 => Ok(WeaponKind::PlasmaRifle),
-            _ => Err(format!("unknown weapon kind {}", id)),
+        match definition::registry::<WeaponDefinition>().get(id) {
+            Some(_) => Ok(WeaponKind(id)),
+            None => Err(format!("unknown weapon kind {}", id)),
+        }
+    }
+
+    /// Returns kind of weapon which is given by picking up an item.
+    pub fn from_item(item: ItemKind) -> Option<Self> {
+        definition::registry::<WeaponDefinition>()
+            .iter()
+            .find(|d| d.item == item.id())
+            .map(|d| WeaponKind(d.id))
+    }
+
+    /// Returns weapons the player has at the start of a level.
+    pub fn starting_weapons() -> Vec<Self> {
+        definition::registry::<WeaponDefinition>()
+            .iter()
+            .filter(|d| d.starting_weapon)
+            .map(|d| WeaponKind(d.id))
+            .collect()
+    }
+}
+
+impl FromStr for WeaponKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match definition::registry::<WeaponDefinition>().find(s) {
+            Some(definition) => Ok(WeaponKind(definition.id)),
+            None => Err(format!("unknown weapon kind {}", s)),
         }
     }
 }
@@ -73,9 +101,16 @@ pub struct Weapon {
     last_shot_time: f64,
     shot_position: Vector3<f32>,
     owner: Handle<Actor>,
+    /// Amount of ammo in magazine.
     ammo: u32,
+    /// Time left until reload is finished.
+    reload_timer: f32,
+    /// Ammo taken from reserve for reload, it is loaded into magazine when reload is finished.
+    reload_amount: u32,
+    /// Degrees that sustained fire has added to min spread.
+    bloom: f32,
     muzzle_flash_timer: f32,
-    pub definition: &'static WeaponDefinition,
+    pub definition: Arc<WeaponDefinition>,
     pub sender: Option<Sender<Message>>,
 }
 
@@ -85,11 +120,19 @@ pub struct Hit {
     pub who: Handle<Actor>,
     pub position: Vector3<f32>,
     pub normal: Vector3<f32>,
+    /// Hitbox of the actor that was hit, None if the body of the actor or geometry was hit.
+    pub location: Option<HitLocation>,
+    /// Damage multiplier of the hitbox.
+    pub damage_multiplier: f32,
 }
 
 impl Hash for Hit {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        utils::hash_as_bytes(self, state);
+        utils::hash_as_bytes(&self.actor, state);
+        utils::hash_as_bytes(&self.who, state);
+        utils::hash_as_bytes(&self.position, state);
+        utils::hash_as_bytes(&self.normal, state);
+        self.location.hash(state);
     }
 }
 
@@ -127,20 +170,38 @@ pub fn ray_hit(
             let collider = physics.colliders.get(hit.collider.into()).unwrap();
             let body = collider.parent();
 
-            // Check if there was an intersection with an actor.
-            for (actor_handle, actor) in actors.pair_iter() {
-                if actor.get_body() == body.into() && weapon.is_some() {
+            // Check if there was an intersection with an actor, actors with hitboxes are hit
+            // only by their hitboxes.
+            let mut location = None;
+            let mut damage_multiplier = 1.0;
+            let actor = actors.pair_iter().find(|(_, actor)| {
+                if let Some(hitbox) = actor.hitboxes.iter().find(|h| h.body() == body.into()) {
+                    location = Some(hitbox.location);
+                    damage_multiplier = hitbox.damage_multiplier;
+                    true
+                } else {
+                    actor.hitboxes.is_empty() && actor.get_body() == body.into()
+                }
+            });
+            if let Some((actor_handle, _)) = actor {
+                if weapons.contains(weapon) {
                     let weapon = &weapons[weapon];
                     // Ignore intersections with owners of weapon.
-                    if weapon.owner() != actor_handle {
-                        return Some(Hit {
-                            actor: actor_handle,
-                            who: weapon.owner(),
-                            position: hit.position.coords,
-                            normal: hit.normal,
-                        });
+                    if weapon.owner() == actor_handle {
+                        continue;
                     }
+                    return Some(Hit {
+                        actor: actor_handle,
+                        who: weapon.owner(),
+                        position: hit.position.coords,
+                        normal: hit.normal,
+                        location,
+                        damage_multiplier,
+                    });
                 }
+            } else if actors.iter().any(|a| a.get_body() == body.into()) || collider.is_sensor() {
+                // Body of an actor with hitboxes and other sensors do not stop rays.
+                continue;
             }
 
             return Some(Hit {
@@ -148,6 +209,8 @@ pub fn ray_hit(
                 who: Handle::NONE,
                 position: hit.position.coords,
                 normal: hit.normal,
+                location: None,
+                damage_multiplier: 1.0,
             });
         }
     }
@@ -155,7 +218,26 @@ pub fn ray_hit(
     None
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Randomly deviates direction within a cone with given half-angle in degrees.
+fn spread_direction(direction: Vector3<f32>, spread: f32, rng: &mut GameRng) -> Vector3<f32> {
+    if spread <= 0.0 {
+        return direction;
+    }
+
+    let side = direction
+        .cross(&Vector3::y())
+        .try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(Vector3::x);
+    // Square root gives uniform distribution of shots over the cone.
+    let deviation = spread.to_radians() * rng.gen::<f32>().sqrt();
+    let roll = rng.gen_range(0.0..std::f32::consts::TAU);
+
+    let deviated =
+        UnitQuaternion::from_axis_angle(&Unit::new_normalize(side), deviation) * direction;
+    UnitQuaternion::from_axis_angle(&Unit::new_normalize(direction), roll) * deviated
+}
+
+#[derive(Copy, Clone, Debug, Deserialize)]
 pub enum WeaponProjectile {
     Projectile(ProjectileKind),
     /// For high-speed "projectiles".
@@ -164,18 +246,96 @@ pub enum WeaponProjectile {
     },
 }
 
+#[derive(Deserialize, Debug)]
 pub struct WeaponDefinition {
-    pub model: &'static str,
-    pub shot_sound: &'static str,
+    /// Unique id of the weapon, it is stored in saves.
+    pub id: u32,
+    /// Unique name of the weapon, it is used by markers of level scenes.
+    pub name: String,
+    pub display_name: String,
+    pub model: String,
+    pub shot_sound: String,
+    /// Id of item definition which is dropped when owner of the weapon dies and gives the
+    /// weapon when picked up.
+    pub item: u32,
+    /// Whether the player has the weapon at the start of a level.
+    #[serde(default)]
+    pub starting_weapon: bool,
+    /// Amount of ammo that is put in reserve with the weapon when it is picked up.
     pub ammo: u32,
+    /// Id of ammo definition the weapon is loaded with.
+    pub ammo_kind: AmmoKind,
+    pub magazine_size: u32,
+    /// Time in seconds it takes to reload the weapon.
+    pub reload_time: f32,
+    /// Half-angle in degrees of spread cone of a single shot.
+    pub min_spread: f32,
+    /// Half-angle in degrees up to which spread cone grows during sustained fire.
+    pub max_spread: f32,
+    /// Degrees that every shot adds to spread.
+    pub spread_per_shot: f32,
+    /// Degrees per second with which spread shrinks back to min spread.
+    pub spread_recovery: f32,
+    /// Degrees by which each shot kicks view of the owner up.
+    pub recoil_pitch: f32,
+    /// Max degrees by which each shot kicks view of the owner aside.
+    pub recoil_yaw: f32,
     pub projectile: WeaponProjectile,
     pub shoot_interval: f64,
 }
 
+static DEFINITIONS: Storage<WeaponDefinition> = RwLock::new(None);
+
+impl Definition for WeaponDefinition {
+    const PATH: &'static str = "data/weapons";
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn storage() -> &'static Storage<Self> {
+        &DEFINITIONS
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        ItemKind::from_id(self.item).map(|_| ())
+    }
+}
+
+impl Default for WeaponDefinition {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: Default::default(),
+            display_name: Default::default(),
+            model: Default::default(),
+            shot_sound: Default::default(),
+            item: 0,
+            starting_weapon: false,
+            ammo: 0,
+            ammo_kind: Default::default(),
+            magazine_size: 0,
+            reload_time: 0.0,
+            min_spread: 0.0,
+            max_spread: 0.0,
+            spread_per_shot: 0.0,
+            spread_recovery: 0.0,
+            recoil_pitch: 0.0,
+            recoil_yaw: 0.0,
+            projectile: WeaponProjectile::Ray { damage: 0.0 },
+            shoot_interval: 0.0,
+        }
+    }
+}
+
 impl Default for Weapon {
     fn default() -> Self {
         Self {
-            kind: WeaponKind::M4,
+            kind: Default::default(),
             model: Handle::NONE,
             offset: Vector3::default(),
             shot_point: Handle::NONE,
@@ -184,8 +344,11 @@ impl Default for Weapon {
             shot_position: Vector3::default(),
             owner: Handle::NONE,
             ammo: 250,
+            reload_timer: 0.0,
+            reload_amount: 0,
+            bloom: 0.0,
             muzzle_flash_timer: 0.0,
-            definition: Self::get_definition(WeaponKind::M4),
+            definition: Default::default(),
             sender: None,
             muzzle_flash: Default::default(),
             shot_light: Default::default(),
@@ -214,45 +377,20 @@ impl Visit for Weapon {
         self.muzzle_flash.visit("MuzzleFlash", visitor)?;
         self.muzzle_flash_timer.visit("MuzzleFlashTimer", visitor)?;
         self.shot_light.visit("ShotLight", visitor)?;
+        visit_since(&mut self.reload_timer, "ReloadTimer", 12, visitor)?;
+        visit_since(&mut self.reload_amount, "ReloadAmount", 12, visitor)?;
+        visit_since(&mut self.bloom, "Bloom", 13, visitor)?;
 
         visitor.leave_region()
     }
 }
 
 impl Weapon {
-    pub fn get_definition(kind: WeaponKind) -> &'static WeaponDefinition {
-        match kind {
-            WeaponKind::M4 => {
-                static DEFINITION: WeaponDefinition = WeaponDefinition {
-                    model: "data/models/m4.FBX",
-                    shot_sound: "data/sounds/m4_shot.ogg",
-                    ammo: 200,
-                    projectile: WeaponProjectile::Ray { damage: 15.0 },
-                    shoot_interval: 0.15,
-                };
-                &DEFINITION
-            }
-            WeaponKind::Ak47 => {
-                static DEFINITION: WeaponDefinition = WeaponDefinition {
-                    model: "data/models/ak47.FBX",
-                    shot_sound: "data/sounds/ak47.ogg",
-                    ammo: 200,
-                    projectile: WeaponProjectile::Ray { damage: 17.0 },
-                    shoot_interval: 0.15,
-                };
-                &DEFINITION
-            }
-            WeaponKind::PlasmaRifle => {
-                static DEFINITION: WeaponDefinition = WeaponDefinition {
-                    model: "data/models/plasma_rifle.fbx",
-                    shot_sound: "data/sounds/plasma_shot.ogg",
-                    ammo: 100,
-                    projectile: WeaponProjectile::Projectile(ProjectileKind::Plasma),
-                    shoot_interval: 0.25,
-                };
-                &DEFINITION
-            }
-        }
+    pub fn get_definition(kind: WeaponKind) -> Arc<WeaponDefinition> {
+        definition::registry::<WeaponDefinition>()
+            .get(kind.id())
+            .cloned()
+            .unwrap_or_else(|| panic!("weapon definition {} is not loaded", kind.id()))
     }
 
     pub async fn new(
@@ -260,13 +398,18 @@ impl Weapon {
         resource_manager: ResourceManager,
         scene: &mut Scene,
         sender: Sender<Message>,
-    ) -> Weapon {
+    ) -> Result<Weapon, String> {
         let definition = Self::get_definition(kind);
 
         let model = resource_manager
-            .request_model(Path::new(definition.model))
+            .request_model(Path::new(&definition.model))
             .await
-            .unwrap()
+            .map_err(|e| {
+                format!(
+                    "Unable to load model {} of {} weapon. Reason: {:?}",
+                    definition.model, definition.name, e
+                )
+            })?
             .instantiate_geometry(scene);
 
         let shot_point = scene.graph.find_by_name(model, "Weapon:ShotPoint");
@@ -274,7 +417,7 @@ impl Weapon {
         if shot_point.is_none() {
             Log::writeln(
                 MessageKind::Warning,
-                format!("Shot point not found for {:?} weapon!", kind),
+                format!("Shot point not found for {} weapon!", definition.name),
             );
         }
 
@@ -283,7 +426,7 @@ impl Weapon {
         let shot_light = if muzzle_flash.is_none() {
             Log::writeln(
                 MessageKind::Warning,
-                format!("Muzzle flash not found for {:?} weapon!", kind),
+                format!("Muzzle flash not found for {} weapon!", definition.name),
             );
             Default::default()
         } else {
@@ -305,17 +448,17 @@ impl Weapon {
             light
         };
 
-        Weapon {
+        Ok(Weapon {
             kind,
             model,
             shot_point,
+            ammo: definition.magazine_size,
             definition,
             muzzle_flash,
             shot_light,
-            ammo: definition.ammo,
             sender: Some(sender),
             ..Default::default()
-        }
+        })
     }
 
     pub fn set_visibility(&self, visibility: bool, graph: &mut Graph) {
@@ -338,6 +481,15 @@ impl Weapon {
             scene.graph[self.muzzle_flash].set_visibility(false);
             scene.graph[self.shot_light].set_visibility(false);
         }
+
+        self.bloom = (self.bloom - self.definition.spread_recovery * dt).max(0.0);
+
+        if self.reload_timer > 0.0 {
+            self.reload_timer -= dt;
+            if self.reload_timer <= 0.0 {
+                self.ammo += std::mem::replace(&mut self.reload_amount, 0);
+            }
+        }
     }
 
     pub fn get_shot_position(&self, graph: &Graph) -> Vector3<f32> {
@@ -361,35 +513,82 @@ impl Weapon {
         graph[self.model].global_transform().basis()
     }
 
-    pub fn add_ammo(&mut self, amount: u32) {
-        self.ammo += amount;
-    }
-
     pub fn ammo(&self) -> u32 {
         self.ammo
     }
 
+    pub fn magazine_size(&self) -> u32 {
+        self.definition.magazine_size
+    }
+
+    /// Returns half-angle in degrees of current spread cone.
+    pub fn spread(&self) -> f32 {
+        self.definition.min_spread + self.bloom
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.reload_timer > 0.0
+    }
+
+    /// Returns true if magazine is not full and the weapon is not reloading already.
+    pub fn can_reload(&self) -> bool {
+        !self.is_reloading() && self.ammo < self.definition.magazine_size
+    }
+
+    /// Starts reload, given amount of ammo must be taken from reserve of owner, it is loaded
+    /// into magazine when reload is finished.
+    pub fn start_reload(&mut self, amount: u32) {
+        self.reload_timer = self.definition.reload_time;
+        self.reload_amount = amount;
+    }
+
+    pub fn ammo_kind(&self) -> AmmoKind {
+        self.definition.ammo_kind
+    }
+
     pub fn owner(&self) -> Handle<Actor> {
         self.owner
     }
 
+    /// Returns true if the weapon shoots projectiles of given kind.
+    pub fn fires(&self, kind: ProjectileKind) -> bool {
+        match self.definition.projectile {
+            WeaponProjectile::Projectile(projectile) => projectile == kind,
+            WeaponProjectile::Ray { .. } => false,
+        }
+    }
+
+    /// Makes the weapon ready to shoot right away.
+    pub fn reset_shot_time(&mut self) {
+        self.last_shot_time = 0.0;
+    }
+
     pub fn set_owner(&mut self, owner: Handle<Actor>) {
         self.owner = owner;
     }
 
+    /// Shoots if the weapon has ammo and is ready, returns true if shot was made. Direction of
+    /// shot is randomized within spread cone scaled by given factor, it is deterministic for
+    /// the same state of random number generator.
+    #[allow(clippy::too_many_arguments)]
     pub fn try_shoot(
         &mut self,
         self_handle: Handle<Weapon>,
         scene: &mut Scene,
-        time: GameTime,
+        level_time: f64,
         resource_manager: ResourceManager,
         direction: Option<Vector3<f32>>,
-    ) {
-        if self.ammo != 0 && time.elapsed - self.last_shot_time >= self.definition.shoot_interval {
+        spread_factor: f32,
+        rng: &mut GameRng,
+    ) -> bool {
+        if self.ammo != 0
+            && !self.is_reloading()
+            && level_time - self.last_shot_time >= self.definition.shoot_interval
+        {
             self.ammo -= 1;
 
             self.offset = Vector3::new(0.0, 0.0, -0.05);
-            self.last_shot_time = time.elapsed;
+            self.last_shot_time = level_time;
 
             let position = self.get_shot_position(&scene.graph);
 
@@ -397,7 +596,7 @@ impl Weapon {
                 .as_ref()
                 .unwrap()
                 .send(Message::PlaySound {
-                    path: PathBuf::from(self.definition.shot_sound),
+                    path: PathBuf::from(&self.definition.shot_sound),
                     position,
                     gain: 1.0,
                     rolloff_factor: 5.0,
@@ -417,9 +616,7 @@ impl Weapon {
                         "data/particles/muzzle_05.png",
                     ];
                     surface.set_diffuse_texture(Some(
-                        resource_manager.request_texture(
-                            textures.choose(&mut rg3d::rand::thread_rng()).unwrap(),
-                        ),
+                        resource_manager.request_texture(textures.choose(rng).unwrap()),
                     ))
                 }
                 scene.graph[self.shot_light].set_visibility(true);
@@ -431,6 +628,11 @@ impl Weapon {
                 .unwrap_or_else(|| self.get_shot_direction(&scene.graph))
                 .try_normalize(std::f32::EPSILON)
                 .unwrap_or_else(|| Vector3::z());
+            let direction = spread_direction(direction, self.spread() * spread_factor, rng);
+
+            self.bloom = (self.bloom + self.definition.spread_per_shot)
+                .min(self.definition.max_spread - self.definition.min_spread)
+                .max(0.0);
 
             match self.definition.projectile {
                 WeaponProjectile::Projectile(projectile) => self
@@ -443,6 +645,7 @@ impl Weapon {
                         direction,
                         owner: self_handle,
                         initial_velocity: Default::default(),
+                        who: self.owner,
                     })
                     .unwrap(),
                 WeaponProjectile::Ray { damage } => {
@@ -459,9 +662,18 @@ impl Weapon {
                         .unwrap();
                 }
             }
+
+            true
+        } else {
+            false
         }
     }
 
+    /// Picks up reloaded definition, model of the weapon stays the same.
+    pub fn refresh_definition(&mut self) {
+        self.definition = Self::get_definition(self.kind);
+    }
+
     pub fn clean_up(&mut self, scene: &mut Scene) {
         scene.graph.remove_node(self.model);
     }
@@ -1,7 +1,11 @@
 use crate::{
-    actor::ActorContainer,
+    actor::{Actor, ActorContainer},
+    character::DamageType,
+    definition::{self, Definition, Storage},
     effects::EffectKind,
     message::Message,
+    rng::GameRng,
+    save::visit_since,
     weapon::{ray_hit, Hit, Weapon, WeaponContainer},
     GameTime,
 };
@@ -11,8 +15,7 @@ use rg3d::{
         algebra::{UnitQuaternion, Vector3},
         color::Color,
         math::Vector3Ext,
-        pool::{Handle, Pool, PoolIteratorMut},
-        rand::Rng,
+        pool::{Handle, Pool, PoolIterator, PoolIteratorMut},
         visitor::{Visit, VisitResult, Visitor},
     },
     engine::resource_manager::ResourceManager,
@@ -21,7 +24,7 @@ use rg3d::{
         geometry::{ColliderBuilder, Proximity, ProximityEvent},
         na::{Isometry3, Translation3},
     },
-    rand,
+    rand::Rng,
     scene::{
         base::BaseBuilder,
         graph::Graph,
@@ -31,12 +34,21 @@ use rg3d::{
         RigidBodyHandle, Scene,
     },
 };
-use std::{collections::HashSet, path::PathBuf, sync::mpsc::Sender};
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{mpsc::Sender, Arc, RwLock},
+};
+
+/// Interval in seconds between puffs of trail of a projectile.
+const TRAIL_INTERVAL: f32 = 0.1;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize)]
 pub enum ProjectileKind {
     Plasma,
     Grenade,
+    Rocket,
 }
 
 impl ProjectileKind {
@@ -44,6 +56,7 @@ impl ProjectileKind {
         match id {
             0 => Ok(ProjectileKind::Plasma),
             1 => Ok(ProjectileKind::Grenade),
+            2 => Ok(ProjectileKind::Rocket),
             _ => Err(format!("Invalid projectile kind id {}", id)),
         }
     }
@@ -52,6 +65,15 @@ impl ProjectileKind {
         match self {
             ProjectileKind::Plasma => 0,
             ProjectileKind::Grenade => 1,
+            ProjectileKind::Rocket => 2,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProjectileKind::Plasma => "Plasma",
+            ProjectileKind::Grenade => "Grenade",
+            ProjectileKind::Rocket => "Rocket",
         }
     }
 }
@@ -69,13 +91,17 @@ pub struct Projectile {
     rotation_angle: f32,
     /// Handle of weapons from which projectile was fired.
     pub owner: Handle<Weapon>,
+    /// Actor who fired the projectile.
+    pub who: Handle<Actor>,
     initial_velocity: Vector3<f32>,
     /// Position of projectile on the previous frame, it is used to simulate
     /// continuous intersection detection from fast moving projectiles.
     last_position: Vector3<f32>,
-    definition: &'static ProjectileDefinition,
+    definition: Arc<ProjectileDefinition>,
     pub sender: Option<Sender<Message>>,
     hits: HashSet<Hit>,
+    /// Time left until next puff of trail.
+    trail_timer: f32,
 }
 
 impl Default for Projectile {
@@ -88,50 +114,78 @@ impl Default for Projectile {
             lifetime: 0.0,
             rotation_angle: 0.0,
             owner: Default::default(),
+            who: Default::default(),
             initial_velocity: Default::default(),
             last_position: Default::default(),
-            definition: Self::get_definition(ProjectileKind::Plasma),
+            definition: Default::default(),
             sender: None,
             hits: Default::default(),
+            trail_timer: 0.0,
         }
     }
 }
 
+#[derive(Deserialize, Debug)]
 pub struct ProjectileDefinition {
+    kind: ProjectileKind,
     damage: f32,
+    /// Type of damage of direct hit, explosions always deal explosion damage.
+    damage_type: DamageType,
     speed: f32,
     lifetime: f32,
     /// Means that movement of projectile controlled by code, not physics.
     /// However projectile still could have rigid body to detect collisions.
     is_kinematic: bool,
-    impact_sound: &'static str,
+    impact_sound: String,
+    /// Radius of explosion at impact point, zero radius means that only hit actor is damaged.
+    #[serde(default)]
+    explosion_radius: f32,
+    /// Effect that is left behind the projectile while it flies.
+    #[serde(default)]
+    trail: Option<EffectKind>,
 }
 
-impl Projectile {
-    pub fn get_definition(kind: ProjectileKind) -> &'static ProjectileDefinition {
-        match kind {
-            ProjectileKind::Plasma => {
-                static DEFINITION: ProjectileDefinition = ProjectileDefinition {
-                    damage: 30.0,
-                    speed: 0.15,
-                    lifetime: 10.0,
-                    is_kinematic: true,
-                    impact_sound: "data/sounds/bullet_impact_concrete.ogg",
-                };
-                &DEFINITION
-            }
-            ProjectileKind::Grenade => {
-                static DEFINITION: ProjectileDefinition = ProjectileDefinition {
-                    damage: 60.0,
-                    speed: 0.0,
-                    lifetime: 10.0,
-                    is_kinematic: false,
-                    impact_sound: "data/sounds/explosion.ogg",
-                };
-                &DEFINITION
-            }
+impl Default for ProjectileDefinition {
+    fn default() -> Self {
+        Self {
+            kind: ProjectileKind::Plasma,
+            damage: 0.0,
+            damage_type: DamageType::Plasma,
+            speed: 0.0,
+            lifetime: 0.0,
+            is_kinematic: false,
+            impact_sound: Default::default(),
+            explosion_radius: 0.0,
+            trail: None,
         }
     }
+}
+
+static DEFINITIONS: Storage<ProjectileDefinition> = RwLock::new(None);
+
+impl Definition for ProjectileDefinition {
+    const PATH: &'static str = "data/projectiles";
+
+    fn id(&self) -> u32 {
+        self.kind.id()
+    }
+
+    fn name(&self) -> &str {
+        self.kind.name()
+    }
+
+    fn storage() -> &'static Storage<Self> {
+        &DEFINITIONS
+    }
+}
+
+impl Projectile {
+    pub fn get_definition(kind: ProjectileKind) -> Arc<ProjectileDefinition> {
+        definition::registry::<ProjectileDefinition>()
+            .get(kind.id())
+            .cloned()
+            .unwrap_or_else(|| panic!("projectile definition {} is not loaded", kind.name()))
+    }
 
     #[allow(clippy::too_many_arguments)]
     pub async fn new(
@@ -141,17 +195,23 @@ impl Projectile {
         dir: Vector3<f32>,
         position: Vector3<f32>,
         owner: Handle<Weapon>,
+        who: Handle<Actor>,
         initial_velocity: Vector3<f32>,
         sender: Sender<Message>,
+        rng: &mut GameRng,
     ) -> Self {
         let definition = Self::get_definition(kind);
 
         let (model, body) = {
             match &kind {
-                ProjectileKind::Plasma => {
-                    let size = rand::thread_rng().gen_range(0.09..0.12);
+                ProjectileKind::Plasma | ProjectileKind::Rocket => {
+                    let size = rng.gen_range(0.09..0.12);
 
-                    let color = Color::opaque(0, 162, 232);
+                    let color = if kind == ProjectileKind::Rocket {
+                        Color::opaque(255, 127, 40)
+                    } else {
+                        Color::opaque(0, 162, 232)
+                    };
                     let model = SpriteBuilder::new(
                         BaseBuilder::new().with_children(&[PointLightBuilder::new(
                             BaseLightBuilder::new(BaseBuilder::new()).with_color(color),
@@ -203,6 +263,7 @@ impl Projectile {
             model,
             last_position: position,
             owner,
+            who,
             definition,
             sender: Some(sender),
             ..Default::default()
@@ -283,61 +344,106 @@ impl Projectile {
         // stabilizes its movement over time.
         self.initial_velocity.follow(&Vector3::default(), 0.15);
 
+        if let Some(trail) = self.definition.trail {
+            self.trail_timer -= time.delta;
+            if self.trail_timer <= 0.0 {
+                self.trail_timer = TRAIL_INTERVAL;
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::CreateEffect {
+                        kind: trail,
+                        position,
+                        orientation: UnitQuaternion::identity(),
+                    })
+                    .unwrap();
+            }
+        }
+
         self.lifetime -= time.delta;
 
         if self.lifetime <= 0.0 {
-            let (pos, normal, effect_kind) = ray_hit.map_or_else(
-                || {
-                    (
-                        self.get_position(&scene.graph),
-                        Vector3::y(),
-                        EffectKind::BulletImpact,
-                    )
-                },
-                |h| {
-                    (
-                        h.position,
-                        h.normal,
-                        if h.actor.is_some() {
-                            EffectKind::BloodSpray
-                        } else {
-                            EffectKind::BulletImpact
-                        },
-                    )
-                },
+            let (pos, normal) = ray_hit.map_or_else(
+                || (self.get_position(&scene.graph), Vector3::y()),
+                |h| (h.position, h.normal),
             );
 
-            self.sender
-                .as_ref()
-                .unwrap()
-                .send(Message::CreateEffect {
-                    kind: effect_kind,
-                    position: pos,
-                    orientation: UnitQuaternion::face_towards(&normal, &Vector3::y()),
-                })
-                .unwrap();
+            // Effects of a hit actor are created by the level when the actor is damaged.
+            let hit_actor = self.hits.iter().any(|h| h.actor.is_some());
+            if !hit_actor || self.definition.explosion_radius > 0.0 {
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::CreateEffect {
+                        kind: EffectKind::BulletImpact,
+                        position: pos,
+                        orientation: UnitQuaternion::face_towards(&normal, &Vector3::y()),
+                    })
+                    .unwrap();
+
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::PlaySound {
+                        path: PathBuf::from(&self.definition.impact_sound),
+                        position: pos,
+                        gain: 1.0,
+                        rolloff_factor: 4.0,
+                        radius: 3.0,
+                    })
+                    .unwrap();
+            }
 
+            if self.definition.explosion_radius > 0.0 {
+                self.sender
+                    .as_ref()
+                    .unwrap()
+                    .send(Message::CreateExplosion {
+                        position: pos,
+                        normal,
+                        radius: self.definition.explosion_radius,
+                        damage: self.definition.damage,
+                        who: self.who,
+                    })
+                    .unwrap();
+            }
+        }
+
+        // Shot counts as a single hit even if the projectile has hit few actors at once. Only
+        // shots of weapons are counted, tossed grenades are not shots.
+        let is_shot = weapons.contains(self.owner) && weapons[self.owner].fires(self.kind);
+        if let Some(hit) = self
+            .hits
+            .iter()
+            .find(|h| h.actor.is_some())
+            .filter(|_| is_shot)
+        {
             self.sender
                 .as_ref()
                 .unwrap()
-                .send(Message::PlaySound {
-                    path: PathBuf::from(self.definition.impact_sound),
-                    position: pos,
-                    gain: 1.0,
-                    rolloff_factor: 4.0,
-                    radius: 3.0,
+                .send(Message::ShotHit {
+                    who: self.who,
+                    location: hit.location,
                 })
                 .unwrap();
         }
 
+        // Explosive projectiles damage hit actor by explosion.
+        if self.definition.explosion_radius > 0.0 {
+            self.hits.clear();
+        }
+
         for hit in self.hits.drain() {
             self.sender
                 .as_ref()
                 .unwrap()
                 .send(Message::DamageActor {
                     actor: hit.actor,
-                    who: hit.who,
-                    amount: self.definition.damage,
+                    who: self.who,
+                    amount: self.definition.damage * hit.damage_multiplier,
+                    damage_type: self.definition.damage_type,
+                    position: Some(hit.position),
+                    location: hit.location,
                 })
                 .unwrap();
         }
@@ -357,8 +463,6 @@ impl Projectile {
         if proximity_event.new_status == Proximity::Intersecting
             || proximity_event.new_status == Proximity::WithinMargin
         {
-            let mut owner_contact = false;
-
             let body_a = scene
                 .physics
                 .colliders
@@ -372,15 +476,29 @@ impl Projectile {
                 .unwrap()
                 .parent();
 
+            // Hitboxes of actors are sensors too, so there are events between other colliders.
+            let other = if body_a == self.body.into() {
+                body_b
+            } else if body_b == self.body.into() {
+                body_a
+            } else {
+                return;
+            };
+
+            let mut keep_flying = false;
+
             // Check if we got contact with any actor and damage it then.
             for (actor_handle, actor) in actors.pair_iter() {
-                if (body_a == actor.get_body().into() && body_b == self.body.into()
-                    || body_b == actor.get_body().into() && body_a == self.body.into())
-                    && self.owner.is_some()
-                {
-                    // Prevent self-damage.
+                if other == actor.get_body().into() && weapons.contains(self.owner) {
                     let weapon = &weapons[self.owner];
-                    if weapon.owner() != actor_handle {
+                    if weapon.owner() == actor_handle {
+                        // Prevent self-damage and make sure that projectile won't die on
+                        // contact with owner.
+                        keep_flying = true;
+                    } else if !actor.hitboxes.is_empty() {
+                        // Projectile flies through the body until its ray test finds a hitbox.
+                        keep_flying = true;
+                    } else {
                         self.hits.insert(Hit {
                             actor: actor_handle,
                             who: weapon.owner(),
@@ -393,20 +511,24 @@ impl Projectile {
                                 .translation
                                 .vector,
                             normal: Vector3::y(),
+                            location: None,
+                            damage_multiplier: 1.0,
                         });
-                    } else {
-                        // Make sure that projectile won't die on contact with owner.
-                        owner_contact = true;
                     }
                 }
             }
 
-            if !owner_contact {
+            if !keep_flying {
                 self.kill();
             }
         }
     }
 
+    /// Picks up reloaded definition, it is applied to the rest of the flight.
+    pub fn refresh_definition(&mut self) {
+        self.definition = Self::get_definition(self.kind);
+    }
+
     pub fn get_position(&self, graph: &Graph) -> Vector3<f32> {
         graph[self.model].global_position()
     }
@@ -439,6 +561,7 @@ impl Visit for Projectile {
         self.rotation_angle.visit("RotationAngle", visitor)?;
         self.initial_velocity.visit("InitialVelocity", visitor)?;
         self.owner.visit("Owner", visitor)?;
+        visit_since(&mut self.who, "Who", 15, visitor)?;
 
         visitor.leave_region()
     }
@@ -461,6 +584,14 @@ impl ProjectileContainer {
         self.pool.iter_mut()
     }
 
+    pub fn iter(&self) -> PoolIterator<Projectile> {
+        self.pool.iter()
+    }
+
+    pub fn count(&self) -> usize {
+        self.pool.alive_count()
+    }
+
     pub fn update(
         &mut self,
         scene: &mut Scene,
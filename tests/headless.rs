@@ -0,0 +1,88 @@
+use rg3d::{
+    core::{algebra::Vector3, pool::Handle},
+    futures::executor::block_on,
+};
+use station_iapetus::{
+    character::DamageType, headless::HeadlessSimulation, item::ItemKind,
+    level::manifest::LevelManifest, message::Message, weapon::projectile::ProjectileKind,
+};
+
+const SEED: u64 = 42;
+
+fn start_level() -> HeadlessSimulation {
+    let manifest = LevelManifest::load(LevelManifest::PATH).unwrap();
+    let definition = manifest.definition(&manifest.start).unwrap().clone();
+    let mut simulation = block_on(HeadlessSimulation::new(definition, SEED)).unwrap();
+    simulation.step();
+    simulation
+}
+
+fn player_position(simulation: &HeadlessSimulation) -> Vector3<f32> {
+    let player = simulation.level.get_player();
+    simulation
+        .actors()
+        .get(player)
+        .position(&simulation.scene.physics)
+}
+
+#[test]
+fn damaged_actor_loses_health() {
+    let mut simulation = start_level();
+    let player = simulation.level.get_player();
+    let health = simulation.actors().get(player).get_health();
+
+    simulation.send(Message::DamageActor {
+        actor: player,
+        who: Handle::NONE,
+        amount: 20.0,
+        damage_type: DamageType::Environment,
+        position: None,
+        location: None,
+    });
+    simulation.step();
+
+    assert!(simulation.actors().get(player).get_health() < health);
+}
+
+#[test]
+fn plasma_shot_creates_projectile() {
+    let mut simulation = start_level();
+    let count = simulation.projectile_count();
+
+    simulation.send(Message::CreateProjectile {
+        kind: ProjectileKind::Plasma,
+        position: player_position(&simulation) + Vector3::new(0.0, 1.0, 0.0),
+        direction: Vector3::z(),
+        initial_velocity: Vector3::default(),
+        owner: Handle::NONE,
+        who: Handle::NONE,
+    });
+    simulation.step();
+
+    assert_eq!(simulation.projectile_count(), count + 1);
+}
+
+#[test]
+fn walking_onto_item_picks_it_up() {
+    let mut simulation = start_level();
+    let count = simulation.item_count();
+
+    let position = player_position(&simulation) + Vector3::new(1.5, 0.0, 0.0);
+    simulation.send(Message::SpawnItem {
+        kind: ItemKind::Plasma,
+        position,
+        adjust_height: false,
+    });
+    simulation.step();
+    assert_eq!(simulation.item_count(), count + 1);
+
+    let player = simulation.level.get_player();
+    simulation
+        .level
+        .actors_mut()
+        .get_mut(player)
+        .set_position(&mut simulation.scene.physics, position);
+    simulation.run(2);
+
+    assert_eq!(simulation.item_count(), count);
+}